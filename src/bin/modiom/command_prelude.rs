@@ -1,7 +1,6 @@
 use std::any::Any;
 use std::borrow::Cow;
 use std::fs;
-use std::io;
 use std::path::{Path, PathBuf};
 
 pub use clap::builder::{
@@ -9,15 +8,19 @@ pub use clap::builder::{
 };
 pub use clap::{value_parser, ArgMatches};
 pub use modiom::config::Config;
+pub use modiom::errors::Error;
 pub use modiom::{CliResult, Result};
 pub use prettytable::{row, table};
 
+use modiom::lockfile::LockedMod;
+use modiom::manifest::{Features, ModDependencies, ModioManifest};
+use modiom::scan;
 use modiom::utils::find_manifest_for_wd;
 
 pub fn client(config: &Config) -> Result<modio::Modio> {
     let token = config
         .auth_token()?
-        .ok_or("authentication token required")?;
+        .ok_or_else(|| Error::auth("authentication token required, run `modiom login` first"))?;
 
     let client = modio::Modio::builder(token)
         .host(config.host())
@@ -26,6 +29,75 @@ pub fn client(config: &Config) -> Result<modio::Modio> {
     Ok(client)
 }
 
+pub fn http_client() -> Result<reqwest::Client> {
+    let client = reqwest::Client::builder().user_agent("modiom").build()?;
+    Ok(client)
+}
+
+/// Returns the cached archive of the modfile and downloads it if necessary. Modfiles that
+/// didn't pass the virus scan are refused, see [`scan::check`].
+pub async fn fetch_modfile(
+    config: &Config,
+    http: &reqwest::Client,
+    file: &modio::types::files::File,
+    allow_unscanned: bool,
+) -> Result<PathBuf> {
+    scan::check(file, allow_unscanned)?;
+    let cache = config.cache();
+    if let Some(archive) = cache.get(file.id, &file.filehash.md5, &file.filename) {
+        println!("Cached: {}", file.filename);
+        return Ok(archive);
+    }
+    if let Some(mirror) = config.mirror() {
+        let (filename, md5) = (&file.filename, &file.filehash.md5);
+        match cache
+            .import(&mirror, file.id, filename, file.filesize, md5)
+            .await
+        {
+            Ok(Some(archive)) => {
+                println!("Mirror: {filename}");
+                return Ok(archive);
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("warning: {e}, downloading `{filename}` instead"),
+        }
+    }
+    println!("Downloading: {}", file.download.binary_url);
+    config.retry().run(|| cache.fetch(http, file)).await
+}
+
+/// Returns the archive of the locked modfile from the download cache or the mirror of the
+/// config. Mods installed from a local source have no archive in either.
+pub async fn cached_archive(config: &Config, locked: &LockedMod) -> Result<Option<PathBuf>> {
+    let Some(file_id) = locked.file else {
+        return Ok(None);
+    };
+    let cache = config.cache();
+    if let Some(archive) = cache.get(file_id, &locked.md5, &locked.filename) {
+        return Ok(Some(archive));
+    }
+    match config.mirror() {
+        Some(mirror) => {
+            let (filename, size) = (&locked.filename, locked.size);
+            cache
+                .import(&mirror, file_id, filename, size, &locked.md5)
+                .await
+        }
+        None => Ok(None),
+    }
+}
+
+/// Returns the archive of the locked modfile in the download cache or the mirror without marking
+/// it as used or copying it, e.g. for a dry run.
+pub fn archive_path(config: &Config, locked: &LockedMod) -> Option<PathBuf> {
+    let file_id = locked.file?;
+    let (md5, filename) = (&locked.md5, &locked.filename);
+    config
+        .cache()
+        .path(file_id, md5, filename)
+        .or_else(|| config.mirror()?.path(file_id, md5, filename))
+}
+
 pub fn opt(name: &'static str, help: &'static str) -> Arg {
     Arg::new(name).long(name).help(help)
 }
@@ -40,6 +112,35 @@ pub trait CommandExt: Sized {
                 .value_parser(ValueParser::path_buf()),
         )
     }
+
+    fn arg_keep_going(self) -> Self {
+        self._arg(
+            opt(
+                "keep-going",
+                "Continue with the remaining mods if one fails and print a summary",
+            )
+            .action(ArgAction::SetTrue),
+        )
+    }
+
+    fn arg_allow_unscanned(self) -> Self {
+        self._arg(
+            opt(
+                "allow-unscanned",
+                "Download modfiles that haven't passed the mod.io virus scan yet",
+            )
+            .action(ArgAction::SetTrue),
+        )
+    }
+
+    fn arg_jobs(self) -> Self {
+        self._arg(
+            opt("jobs", "Number of parallel downloads")
+                .short('j')
+                .value_name("N")
+                .value_parser(value_parser!(u32).range(1..)),
+        )
+    }
 }
 
 impl CommandExt for Command {
@@ -53,27 +154,54 @@ pub trait ArgMatchesExt {
 
     fn _get_one<T: Any + Clone + Send + Sync + 'static>(&self, _: &str) -> Option<&T>;
 
+    fn _get_strings(&self, _: &str) -> Vec<String>;
+
     fn is_test_env(&self) -> bool {
         self._get_flag("test-env")
     }
 
-    fn root_manifest(&self, config: &Config) -> io::Result<Cow<Path>> {
+    fn root_manifest(&self, config: &Config) -> Result<Cow<'_, Path>> {
         if let Some(path) = self.get_path("manifest-path") {
             if !path.ends_with("Modio.toml") {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    "the manifest-path must be a path to a Modio.toml file",
-                ));
+                return Err(
+                    Error::usage("the manifest-path must be a path to a Modio.toml file").into(),
+                );
             }
             if fs::metadata(path).is_err() {
-                return Err(io::Error::new(
-                    io::ErrorKind::NotFound,
-                    format!("manifest-path `{}` does not exist", path.display()),
-                ));
+                return Err(Error::usage(format!(
+                    "manifest-path `{}` does not exist",
+                    path.display()
+                ))
+                .into());
             }
             return Ok(path.into());
         }
-        find_manifest_for_wd(config.cwd()).map(Cow::from)
+        find_manifest_for_wd(config.cwd())
+            .map(Cow::from)
+            .map_err(|e| Error::usage(e.to_string()).into())
+    }
+
+    fn jobs(&self, config: &Config) -> usize {
+        self._get_one::<u32>("jobs")
+            .map(|jobs| *jobs as usize)
+            .unwrap_or_else(|| config.jobs())
+    }
+
+    /// Returns the mods of the manifest selected by `--profile`, `--features` and
+    /// `--all-features`.
+    fn selected_mods(&self, manifest: &ModioManifest) -> Result<ModDependencies> {
+        let profile = self.get_string("profile").map(String::as_str);
+        let features = self._get_strings("features");
+        let features = if self._get_flag("all-features") {
+            Features::All
+        } else {
+            Features::Selected(&features)
+        };
+        let mods = manifest.select_mods(profile, features)?;
+        if mods.is_empty() {
+            return Err(Error::usage("no mods defined").into());
+        }
+        Ok(mods)
     }
 
     fn get_string(&self, id: &str) -> Option<&String> {
@@ -93,4 +221,10 @@ impl ArgMatchesExt for ArgMatches {
     fn _get_one<T: Any + Clone + Send + Sync + 'static>(&self, id: &str) -> Option<&T> {
         self.get_one(id)
     }
+
+    fn _get_strings(&self, id: &str) -> Vec<String> {
+        self.get_many::<String>(id)
+            .map(|v| v.cloned().collect())
+            .unwrap_or_default()
+    }
 }
@@ -0,0 +1,129 @@
+use modiom::edit::ManifestEditor;
+use modiom::manifest::{self, DetailedModDependency, Extract, Identifier, ModDependency};
+use tokio::runtime::Runtime;
+
+use crate::command_prelude::*;
+use crate::resolve::Resolver;
+
+pub fn cli() -> Command {
+    Command::new("add")
+        .about("Add a mod to Modio.toml")
+        .arg(
+            Arg::new("mod")
+                .help("Name-id or unique id of the mod.")
+                .value_name("MOD")
+                .required(true),
+        )
+        .arg(
+            opt(
+                "name",
+                "Key of the mod in the manifest [default: name-id of the mod]",
+            )
+            .value_name("NAME"),
+        )
+        .arg(
+            opt("version", "Install the latest file with the version")
+                .value_name("VERSION")
+                .conflicts_with("file"),
+        )
+        .arg(
+            opt("file", "Install the file with the id")
+                .value_name("ID")
+                .value_parser(value_parser!(u32)),
+        )
+        .arg(
+            opt("with-dependencies", "Install the dependencies of the mod")
+                .action(ArgAction::SetTrue),
+        )
+        .arg_manifest_path()
+}
+
+pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
+    let path = args.root_manifest(config)?;
+    let manifest = manifest::read(&path)?;
+    let mut editor = ManifestEditor::open(&path)?;
+
+    let id = Identifier::from(args.get_string("mod").expect("required arg").as_str());
+    let version = args.get_string("version").cloned();
+    let file = args.get_one::<u32>("file").copied();
+    let with_dependencies = args.get_flag("with-dependencies").then_some(true);
+
+    let dep = DetailedModDependency {
+        id: Some(id),
+        with_dependencies,
+        file,
+        version,
+        priority: None,
+        local: None,
+        extract: Extract::default(),
+    };
+
+    let rt = Runtime::new()?;
+    let modio = client(config)?;
+    let resolver = Resolver {
+        config,
+        modio: &modio,
+        jobs: 1,
+        keep_going: false,
+        policy: None,
+        dependency_graph: false,
+    };
+    let game_id = rt.block_on(resolver.game_id(&manifest.game.id))?;
+    let (mut mod_, mut file) =
+        rt.block_on(resolver.resolve_mod(game_id, &ModDependency::Detailed(dep.clone())))?;
+
+    let name = args.get_string("name").unwrap_or(&mod_.name_id).clone();
+
+    // The options of an existing entry are kept, only the given options are updated.
+    let existing = manifest.mods.as_ref().and_then(|mods| mods.get(&name));
+    let dep = match existing {
+        Some(ModDependency::Detailed(existing)) => {
+            let mut merged = existing.clone();
+            // The mod is installed from mod.io instead of a local source.
+            merged.id = dep.id;
+            merged.local = None;
+            let pinned = dep.version.is_some() || dep.file.is_some();
+            if pinned {
+                merged.version = dep.version;
+                merged.file = dep.file;
+            }
+            if dep.with_dependencies.is_some() {
+                merged.with_dependencies = dep.with_dependencies;
+            }
+            // Look up the modfile pinned by the existing entry.
+            if !pinned && (merged.version.is_some() || merged.file.is_some()) {
+                (mod_, file) = rt.block_on(
+                    resolver.resolve_mod(game_id, &ModDependency::Detailed(merged.clone())),
+                )?;
+            }
+            merged
+        }
+        _ => dep,
+    };
+    let dep = match dep {
+        DetailedModDependency {
+            id: Some(id),
+            with_dependencies: None,
+            file: None,
+            version: None,
+            priority: None,
+            local: None,
+            extract,
+        } if !extract.is_enabled() => ModDependency::Simple(id),
+        dep => ModDependency::Detailed(dep),
+    };
+    let action = if editor.contains_mod(&name) {
+        "Updating"
+    } else {
+        "Adding"
+    };
+    editor.insert_mod(&name, &dep)?;
+    editor.write()?;
+
+    let version = file.version.as_deref().unwrap_or("-");
+    println!(
+        "{action}: {name} ({}, {}, version: {version})",
+        mod_.name, file.filename
+    );
+    Ok(())
+}
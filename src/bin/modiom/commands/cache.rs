@@ -0,0 +1,103 @@
+use std::time::{Duration, SystemTime};
+
+use prettytable::{format, Table};
+
+use modiom::utils::{format_size, parse_size};
+
+use crate::command_prelude::*;
+
+pub fn cli() -> Command {
+    Command::new("cache")
+        .about("Manage the download cache")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(Command::new("list").about("List the cached modfiles"))
+        .subcommand(Command::new("clean").about("Remove all cached modfiles"))
+        .subcommand(
+            Command::new("prune")
+                .about("Remove old cached modfiles")
+                .arg(
+                    opt(
+                        "max-size",
+                        "Remove the least recently used files above SIZE.",
+                    )
+                    .value_name("SIZE")
+                    .value_parser(|s: &str| parse_size(s).ok_or("invalid size")),
+                )
+                .arg(
+                    opt("max-age", "Remove files not used within the last DAYS.")
+                        .value_name("DAYS")
+                        .value_parser(value_parser!(u64)),
+                )
+                .group(
+                    clap::ArgGroup::new("limits")
+                        .args(["max-size", "max-age"])
+                        .multiple(true)
+                        .required(true),
+                ),
+        )
+}
+
+pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
+    match args.subcommand() {
+        Some(("list", _)) => list(config),
+        Some(("clean", _)) => clean(config),
+        Some(("prune", matches)) => prune(config, matches),
+        _ => unreachable!(),
+    }
+}
+
+fn list(config: &Config) -> CliResult {
+    let entries = config.cache().entries()?;
+    let now = SystemTime::now();
+
+    let mut output = Table::new();
+    output.set_format(*format::consts::FORMAT_CLEAN);
+    output.set_titles(row![
+        b -> "File",
+        b -> "MD5",
+        b -> "Filename",
+        b -> "Size",
+        b -> "Last used",
+    ]);
+    let mut total = 0;
+    for e in &entries {
+        total += e.size;
+        let age = now.duration_since(e.last_used).unwrap_or_default();
+        output.add_row(row![
+            e.file_id,
+            e.md5,
+            e.filename,
+            r -> format_size(e.size),
+            r -> format!("{} days ago", age.as_secs() / 86400),
+        ]);
+    }
+    if output.is_empty() {
+        output.add_row(row![H5 -> "No cached files"]);
+    }
+    output.printstd();
+    println!("Total: {} in {} files", format_size(total), entries.len());
+    Ok(())
+}
+
+fn clean(config: &Config) -> CliResult {
+    let cache = config.cache();
+    cache.clean()?;
+    println!("Removed {}", cache.root().display());
+    Ok(())
+}
+
+fn prune(config: &Config, args: &ArgMatches) -> CliResult {
+    let max_size = args.get_one::<u64>("max-size").copied();
+    let max_age = args
+        .get_one::<u64>("max-age")
+        .map(|days| Duration::from_secs(days.saturating_mul(86400)));
+
+    let removed = config.cache().prune(max_size, max_age)?;
+    for e in &removed {
+        println!("Removed: {} ({})", e.filename, e.file_id);
+    }
+    let freed = removed.iter().map(|e| e.size).sum();
+    println!("Freed {} in {} files", format_size(freed), removed.len());
+    Ok(())
+}
@@ -0,0 +1,157 @@
+use modio::types::id::ModId;
+use modiom::errors::{kind_of, ErrorKind};
+use modiom::extract::ExtractOptions;
+use modiom::manifest::{self, Features, ModDependency};
+use modiom::utils;
+use modiom::version::VersionSpec;
+use std::path::Path;
+use tokio::runtime::Runtime;
+
+use crate::command_prelude::*;
+use crate::resolve::Resolver;
+
+pub fn cli() -> Command {
+    Command::new("check")
+        .about("Check Modio.toml for errors")
+        .arg(
+            opt(
+                "offline",
+                "Only check the syntax without looking up the game and mods.",
+            )
+            .action(ArgAction::SetTrue),
+        )
+        .arg_manifest_path()
+}
+
+pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
+    let path = args.root_manifest(config)?;
+    let content = utils::read(&path)?;
+    let manifest = manifest::parse(&content, &path)?;
+
+    // Unused keys and an empty `[mods]` table are usually typos, so they count as problems.
+    let mut problems = 0;
+    let mut kind = ErrorKind::Usage;
+    for key in manifest::unknown_keys(&content) {
+        eprintln!("error: unused manifest key: {key}");
+        problems += 1;
+    }
+    let mut mods = manifest
+        .select_mods(None, Features::All)?
+        .into_iter()
+        .map(|(name, dep)| (format!("mods.{name}"), name, dep))
+        .collect::<Vec<_>>();
+    if mods.is_empty() {
+        eprintln!("error: no mods defined");
+        problems += 1;
+    }
+    for (name, profile) in &manifest.profile {
+        manifest.select_mods(Some(name), Features::All)?;
+        for (m, dep) in profile.mods.iter().flatten() {
+            mods.push((format!("profile.{name}.mods.{m}"), m.clone(), dep.clone()));
+        }
+    }
+    mods.retain(|(name, key, dep)| match check_syntax(key, dep) {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("error: {name}: {e}");
+            problems += 1;
+            false
+        }
+    });
+    if args.get_flag("offline") {
+        return finish(&path, problems, kind);
+    }
+
+    let rt = Runtime::new()?;
+    let modio = client(config)?;
+    let resolver = Resolver {
+        config,
+        modio: &modio,
+        jobs: 1,
+        keep_going: true,
+        policy: None,
+        dependency_graph: false,
+    };
+
+    let game_id = rt.block_on(resolver.game_id(&manifest.game.id))?;
+    let root = path.parent().unwrap_or_else(|| Path::new("."));
+    for (name, key, dep) in mods {
+        // Mods with a local source aren't looked up on mod.io.
+        if let Some(source) = dep.local() {
+            if root.join(source).exists() {
+                println!("Ok: {name} (local source `{source}`)");
+            } else {
+                eprintln!("error: {name}: local source `{source}` not found");
+                problems += 1;
+                kind = ErrorKind::NotFound;
+            }
+            continue;
+        }
+        match rt.block_on(resolver.resolve_mod(game_id, &dep)) {
+            Ok((mod_, file)) => {
+                if let Some(extract) = dep.extract() {
+                    let id = Some(mod_.id);
+                    if let Err(e) = ExtractOptions::new(extract, &key, &mod_.name_id, id) {
+                        eprintln!("error: {name}: {e}");
+                        problems += 1;
+                        continue;
+                    }
+                }
+                let version = file.version.as_deref().unwrap_or("-");
+                println!("Ok: {name} ({}, version: {version})", file.filename);
+            }
+            Err(e) if kind_of(&*e) == ErrorKind::NotFound => {
+                eprintln!("error: {name}: {e}");
+                problems += 1;
+                kind = ErrorKind::NotFound;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    finish(&path, problems, kind)
+}
+
+/// Check the version requirement and the extract options of a mod without looking it up.
+fn check_syntax(key: &str, dep: &ModDependency) -> modiom::Result<()> {
+    if let Some(version) = dep.version() {
+        VersionSpec::parse(version)?;
+    }
+    if let Some(extract) = dep.extract() {
+        // The name id and the id of the mod aren't known yet, placeholders are enough to check
+        // the target path and the glob patterns.
+        let id = dep.local().is_none().then(|| ModId::new(1));
+        ExtractOptions::new(extract, key, key, id)?;
+    }
+    Ok(())
+}
+
+fn finish(path: &Path, problems: usize, kind: ErrorKind) -> CliResult {
+    if problems > 0 {
+        let msg = format!("{problems} problem(s) found in `{}`", path.display());
+        return Err(Error::new(kind, msg).into());
+    }
+    println!("Checked: {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn dep(toml: &str) -> ModDependency {
+        let content = format!("[game]\nid = 1\n\n[mods]\nm = {toml}\n");
+        let manifest = manifest::parse(&content, Path::new("Modio.toml")).unwrap();
+        manifest.mods.unwrap().into_iter().next().unwrap().1
+    }
+
+    #[test]
+    fn syntax() {
+        assert!(check_syntax("m", &dep(r#"{ id = 1, version = ">=1.2" }"#)).is_ok());
+        assert!(check_syntax("m", &dep(r#"{ id = 1, version = ">=abc" }"#)).is_err());
+        assert!(check_syntax("m", &dep(r#"{ id = 1, path = "mods/{id}" }"#)).is_ok());
+        assert!(check_syntax("m", &dep(r#"{ id = 1, path = "../mods" }"#)).is_err());
+        assert!(check_syntax("m", &dep(r#"{ id = 1, include = ["[a"] }"#)).is_err());
+        assert!(check_syntax("m", &dep(r#"{ path = "src", target = "{id}" }"#)).is_err());
+    }
+}
@@ -1,12 +1,17 @@
 use std::borrow::Cow;
 use std::collections::HashSet;
 
+use futures::{stream, StreamExt};
+use tokio::fs;
 use tokio::runtime::Runtime;
 
 use modio::filter::prelude::*;
 use modio::types::id::{GameId, ModId};
+use modiom::scan;
 
 use crate::command_prelude::*;
+use crate::plan::{Action, Plan};
+use crate::summary::Summary;
 
 pub fn cli() -> Command {
     Command::new("download")
@@ -33,6 +38,16 @@ pub fn cli() -> Command {
                 .value_name("DEST")
                 .value_parser(ValueParser::path_buf()),
         )
+        .arg(
+            opt(
+                "dry-run",
+                "Print the files that would be downloaded without downloading them.",
+            )
+            .action(ArgAction::SetTrue),
+        )
+        .arg_allow_unscanned()
+        .arg_keep_going()
+        .arg_jobs()
 }
 
 pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
@@ -42,29 +57,135 @@ pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
 
     let rt = Runtime::new()?;
     let modio_ = client(config)?;
+    let http = http_client()?;
 
     let mod_ids = mod_ids.copied().collect::<Vec<_>>();
-    let mut missing_mods: HashSet<ModId> = HashSet::new();
-    missing_mods.extend(&mod_ids);
-
-    let filter = Id::_in(mod_ids);
+    let filter = Id::_in(mod_ids.clone());
+    let allow_unscanned = args.get_flag("allow-unscanned");
+    let policy = config.policy();
 
-    let list = rt.block_on(modio_.game(game_id).mods().search(filter).first_page());
+    let retry = config.retry();
+    let mods = rt.block_on(retry.run(|| {
+        let filter = filter.clone();
+        modio_.game(game_id).mods().search(filter).first_page()
+    }))?;
+    let missing_mods = not_found(&mod_ids, mods.iter().map(|m| m.id));
 
-    if let Ok(mods) = list {
+    if args.get_flag("dry-run") {
+        let cache = config.cache();
+        let mut plan = Plan::default();
         for m in mods {
-            if let Some(file) = m.modfile {
-                println!("Downloading: {}", file.download.binary_url);
+            if let Err(e) = policy.check(&m) {
+                println!("Skipping: {e}");
+                continue;
+            }
+            let Some(file) = m.modfile else {
+                println!("Skipping: {} has no primary file", m.name_id);
+                continue;
+            };
+            if let Err(e) = scan::check(&file, allow_unscanned) {
+                println!("Skipping: {e}");
+                continue;
+            }
+            let action = if cache.contains(file.id, &file.filehash.md5, &file.filename) {
+                Action::Cached
+            } else {
+                Action::Download
+            };
+            plan.add(
+                m.name_id,
+                file.version,
+                file.filename,
+                file.filesize,
+                action,
+            );
+        }
+        plan.print();
+        for mm in missing_mods {
+            println!("Skipping: mod {mm} does not exist");
+        }
+        return Ok(());
+    }
 
-                let out = dest.join(&file.filename);
-                rt.block_on(async { modio_.download(file).await?.save_to_file(out).await })?;
+    let keep_going = args.get_flag("keep-going");
+    let mut summary = Summary::default();
+
+    let mut tasks = vec![];
+    for m in mods {
+        if let Err(e) = policy.check(&m) {
+            if !keep_going {
+                return Err(e.into());
             }
-            missing_mods.remove(&m.id);
+            summary.failed(m.name_id, e.to_string());
+            continue;
+        }
+        let Some(file) = m.modfile else {
+            summary.skipped(m.name_id, "no primary file");
+            continue;
+        };
+        let name = m.name_id;
+        let (http, dest) = (&http, &dest);
+        tasks.push(async move {
+            let res = async {
+                let archive = fetch_modfile(config, http, &file, allow_unscanned).await?;
+                fs::copy(archive, dest.join(&file.filename)).await?;
+                Ok::<_, Box<dyn std::error::Error>>(file.filename)
+            };
+            (name, res.await)
+        });
+    }
+    let mut st = stream::iter(tasks).buffer_unordered(args.jobs(config));
+    while let Some((name, res)) = rt.block_on(st.next()) {
+        match res {
+            Ok(filename) => summary.succeeded(name, format!("downloaded {filename}")),
+            Err(e) if keep_going => summary.failed(name, e.to_string()),
+            Err(e) => return Err(e.into()),
         }
     }
-    for mm in missing_mods {
-        println!("Mod.id: {mm} does not exist or has no primary file.");
+    if keep_going {
+        for mm in missing_mods {
+            summary.failed(mm.to_string(), "mod does not exist");
+        }
+        summary.print();
+        return summary.into_result();
+    }
+    if !missing_mods.is_empty() {
+        let ids = missing_mods
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(Error::not_found(format!("mods do not exist: {ids}")).into());
     }
-
     Ok(())
 }
+
+/// Returns the requested mods that weren't found, sorted by id.
+fn not_found<I>(requested: &[ModId], found: I) -> Vec<ModId>
+where
+    I: IntoIterator<Item = ModId>,
+{
+    let found = found.into_iter().collect::<HashSet<_>>();
+    let mut missing = requested
+        .iter()
+        .filter(|id| !found.contains(id))
+        .copied()
+        .collect::<Vec<_>>();
+    missing.sort();
+    missing.dedup();
+    missing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_mods() {
+        let ids = |ids: &[u64]| ids.iter().map(|id| ModId::new(*id)).collect::<Vec<_>>();
+        let requested = ids(&[3, 1, 2, 3]);
+        assert!(not_found(&requested, ids(&[1, 2, 3])).is_empty());
+        assert_eq!(not_found(&requested, ids(&[2])), ids(&[1, 3]));
+        assert_eq!(not_found(&requested, []), ids(&[1, 2, 3]));
+    }
+}
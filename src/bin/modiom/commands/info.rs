@@ -3,8 +3,10 @@ use prettytable::format;
 use textwrap::fill;
 use tokio::runtime::Runtime;
 
-use modio::filter::Filter;
+use modio::filter::prelude::*;
 use modio::types::id::{GameId, ModId};
+use modiom::policy;
+use modiom::scan::Scan;
 
 use crate::command_prelude::*;
 
@@ -35,112 +37,127 @@ pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
 
     let rt = Runtime::new()?;
     let modio = client(config)?;
+    let retry = config.retry();
 
     let modref = modio.mod_(game_id, mod_id);
 
     let files = async {
         if args.get_flag("files") {
-            let f = Filter::default();
-            modref.files().search(f).first_page().map_ok(Some).await
+            let files = modref.files();
+            retry
+                .run(|| files.search(Filter::default()).first_page())
+                .map_ok(Some)
+                .await
         } else {
             Ok(None)
         }
     };
 
-    let modref = modio.mod_(game_id, mod_id);
-
     let stats = async {
         if args.get_flag("stats") {
-            modref.statistics().map_ok(Some).await
+            retry.run(|| modref.clone().statistics()).map_ok(Some).await
         } else {
             Ok(None)
         }
     };
 
-    let modref = modio.mod_(game_id, mod_id);
-    let deps = modref.dependencies().list();
-    let mod_ = modref.get();
+    let deps = retry.run(|| modref.dependencies().list());
+    let mod_ = retry.run(|| modref.clone().get());
     let task = future::try_join4(mod_, deps, stats, files);
 
-    match rt.block_on(task) {
-        Ok((m, deps, stats, files)) => {
-            let tags = m
-                .tags
-                .iter()
-                .map(|t| format!("{:?}", t.name))
-                .collect::<Vec<_>>()
-                .join(", ");
-            let deps = deps.into_iter().map(|d| d.mod_id).collect::<Vec<_>>();
+    let (m, deps, stats, files) = rt.block_on(task)?;
+    let tags = m
+        .tags
+        .iter()
+        .map(|t| format!("{:?}", t.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let deps = deps.into_iter().map(|d| d.mod_id).collect::<Vec<_>>();
+    let deps = if deps.is_empty() {
+        String::new()
+    } else {
+        let mods = modio.game(game_id).mods();
+        let found = rt.block_on(retry.run(|| mods.search(Id::_in(deps.clone())).collect()))?;
+        deps.iter()
+            .map(|id| match found.iter().find(|m| m.id == *id) {
+                Some(m) => format!("{} ({id})", m.name_id),
+                None => id.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
 
-            let mut mt = table!(
-                [b -> "Id", m.id],
-                [b -> "Name-Id", m.name_id],
-                [b -> "Name", m.name],
-                [b -> "Summary", fill(&m.summary, 60)],
-                [b -> "Profile", m.profile_url],
-                [b -> "Homepage", m.homepage_url.map(|u| u.to_string()).unwrap_or_default()],
-                [b -> "Tags", format!("[{tags}]")],
-                [b -> "Dependencies", format!("{deps:?}")]
-            );
-            let mut primary = None;
-            mt.set_format(*format::consts::FORMAT_CLEAN);
-            if let Some(file) = m.modfile {
-                primary = Some(file.id);
-                mt.add_empty_row();
-                mt.add_row(row![bH2 -> "File"]);
-                mt.add_row(row![b -> "Id", file.id]);
-                mt.add_row(row![b -> "Filename", file.filename]);
-                mt.add_row(row![b -> "Version", file.version.unwrap_or_default()]);
-                mt.add_row(row![b -> "Download", file.download.binary_url]);
-                mt.add_row(row![b -> "Size", file.filesize]);
-                mt.add_row(row![b -> "MD5", file.filehash.md5]);
-            }
-            if let Some(stats) = stats {
-                mt.add_empty_row();
-                mt.add_row(row![bH2 -> "Statistics"]);
-                mt.add_row(row![b -> "Downloads", stats.downloads_total]);
-                mt.add_row(row![b -> "Subscribers", stats.subscribers_total]);
-                mt.add_row(row![
-                    b -> "Rank",
-                    format!(
-                        "{}/{}",
-                        stats.popularity.rank_position,
-                        stats.popularity.rank_total,
-                    )
-                ]);
-                mt.add_row(row![
-                    b -> "Ratings",
-                    format!(
-                        "{} / total: {} positive: {} negative: {}",
-                        stats.ratings.display_text,
-                        stats.ratings.total,
-                        stats.ratings.positive,
-                        stats.ratings.negative,
-                    )
-                ]);
-            }
-            mt.printstd();
+    let mut mt = table!(
+        [b -> "Id", m.id],
+        [b -> "Name-Id", m.name_id],
+        [b -> "Name", m.name],
+        [b -> "Summary", fill(&m.summary, 60)],
+        [b -> "Profile", m.profile_url],
+        [b -> "Homepage", m.homepage_url.map(|u| u.to_string()).unwrap_or_default()],
+        [b -> "Tags", format!("[{tags}]")],
+        [b -> "Maturity", policy::describe(m.maturity_option)],
+        [b -> "Dependencies", fill(&deps, 60)]
+    );
+    let mut primary = None;
+    mt.set_format(*format::consts::FORMAT_CLEAN);
+    if let Some(file) = m.modfile {
+        primary = Some(file.id);
+        let scan = Scan::of(&file);
+        mt.add_empty_row();
+        mt.add_row(row![bH2 -> "File"]);
+        mt.add_row(row![b -> "Id", file.id]);
+        mt.add_row(row![b -> "Filename", file.filename]);
+        mt.add_row(row![b -> "Version", file.version.unwrap_or_default()]);
+        mt.add_row(row![b -> "Download", file.download.binary_url]);
+        mt.add_row(row![b -> "Size", file.filesize]);
+        mt.add_row(row![b -> "MD5", file.filehash.md5]);
+        mt.add_row(row![b -> "Virus scan", scan]);
+    }
+    if let Some(stats) = stats {
+        mt.add_empty_row();
+        mt.add_row(row![bH2 -> "Statistics"]);
+        mt.add_row(row![b -> "Downloads", stats.downloads_total]);
+        mt.add_row(row![b -> "Subscribers", stats.subscribers_total]);
+        mt.add_row(row![
+            b -> "Rank",
+            format!(
+                "{}/{}",
+                stats.popularity.rank_position,
+                stats.popularity.rank_total,
+            )
+        ]);
+        mt.add_row(row![
+            b -> "Ratings",
+            format!(
+                "{} / total: {} positive: {} negative: {}",
+                stats.ratings.display_text,
+                stats.ratings.total,
+                stats.ratings.positive,
+                stats.ratings.negative,
+            )
+        ]);
+    }
+    mt.printstd();
 
-            if let Some(files) = files {
-                let mut ft = table!(
-                    [],
-                    [bH4 -> "Files"],
-                    [b -> "Id", b -> "Filename", b -> "Version", b -> "Download"]
-                );
-                ft.set_format(*format::consts::FORMAT_CLEAN);
-                for file in files {
-                    let suffix = if primary == Some(file.id) { "*" } else { "" };
-                    ft.add_row(row![
-                        format!("{}{suffix}", file.id),
-                        file.filename,
-                        file.version.unwrap_or_default(),
-                        file.download.binary_url
-                    ]);
-                }
-                ft.printstd();
-            }
+    if let Some(files) = files {
+        let mut ft = table!(
+            [],
+            [bH5 -> "Files"],
+            [b -> "Id", b -> "Filename", b -> "Version", b -> "Virus scan", b -> "Download"]
+        );
+        ft.set_format(*format::consts::FORMAT_CLEAN);
+        for file in files {
+            let suffix = if primary == Some(file.id) { "*" } else { "" };
+            let scan = Scan::of(&file);
+            ft.add_row(row![
+                format!("{}{suffix}", file.id),
+                file.filename,
+                file.version.unwrap_or_default(),
+                scan,
+                file.download.binary_url
+            ]);
         }
-        Err(e) => println!("{e}"),
-    };
+        ft.printstd();
+    }
     Ok(())
 }
@@ -0,0 +1,102 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::fs;
+
+use modio::filter::prelude::*;
+use modio::user::filters::subscriptions::GameId;
+use modiom::edit::identifier;
+use modiom::manifest::Identifier;
+use tokio::runtime::Runtime;
+use toml_edit::{Key, Value};
+
+use crate::command_prelude::*;
+use crate::commands::subs::subscriptions;
+
+pub fn cli() -> Command {
+    Command::new("init")
+        .about("Create a new Modio.toml")
+        .arg(
+            Arg::new("game")
+                .help("Name-id or unique id of the game.")
+                .value_name("GAME")
+                .required(true),
+        )
+        .arg(
+            Arg::new("path")
+                .help("Directory of the new manifest [default: current directory]")
+                .value_name("PATH")
+                .value_parser(ValueParser::path_buf()),
+        )
+        .arg(
+            opt(
+                "subscriptions",
+                "Add the subscribed mods of the game to the manifest",
+            )
+            .action(ArgAction::SetTrue),
+        )
+        .arg(
+            opt(
+                "with-dependencies",
+                "Install the dependencies of all mods by default",
+            )
+            .action(ArgAction::SetTrue),
+        )
+}
+
+pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
+    let id = Identifier::from(args.get_string("game").expect("required arg").as_str());
+    let dir = args
+        .get_path("path")
+        .map(Cow::from)
+        .unwrap_or_else(|| config.cwd().into());
+    let path = dir.join("Modio.toml");
+    if path.exists() {
+        return Err(Error::usage(format!("`{}` already exists", path.display())).into());
+    }
+
+    let rt = Runtime::new()?;
+    let modio = client(config)?;
+    let retry = config.retry();
+
+    let filter = match &id {
+        Identifier::Id(id) => Id::eq(id),
+        Identifier::NameId(id) => NameId::eq(id),
+    };
+    let games = modio.games();
+    let game = rt.block_on(retry.run(|| games.search(filter.clone()).first()))?;
+    let game = game.ok_or_else(|| Error::not_found(format!("game `{id}` not found")))?;
+
+    let mut mods = vec![];
+    if args.get_flag("subscriptions") {
+        let filter = GameId::eq(game.id);
+        let subs = rt.block_on(retry.run(|| subscriptions(&modio, filter.clone())))?;
+        mods = subs.into_iter().flat_map(|(_, mods)| mods).collect();
+        mods.sort_by(|a, b| a.name_id.cmp(&b.name_id));
+    }
+
+    let mut content = String::new();
+    writeln!(content, "[game]")?;
+    writeln!(content, "id = {} # {}", identifier(&id), game.name)?;
+    if args.get_flag("with-dependencies") {
+        writeln!(content, "with-dependencies = true")?;
+    }
+    writeln!(content, "\n[mods]")?;
+    if mods.is_empty() {
+        writeln!(content, "# mod-name = \"mod-name-id\"")?;
+    }
+    for m in &mods {
+        let key = Key::new(m.name_id.as_str());
+        let value = Value::from(m.name_id.as_str());
+        writeln!(content, "{} = {value} # {}", key.display_repr(), m.name)?;
+    }
+
+    fs::create_dir_all(&dir)?;
+    fs::write(&path, content)?;
+    println!(
+        "Created: {} for {} with {} mod(s)",
+        path.display(),
+        game.name,
+        mods.len()
+    );
+    Ok(())
+}
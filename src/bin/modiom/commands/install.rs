@@ -1,80 +1,973 @@
-use std::path::Path;
+use std::collections::{HashSet, VecDeque};
+use std::error::Error as StdError;
+use std::path::{Path, PathBuf};
 
-use futures::{stream, TryStreamExt};
-use modio::filter::prelude::*;
-use modio::types::id::GameId;
-use modiom::manifest::{self, Identifier};
+use futures::{stream, StreamExt};
+use modio::types::files::File;
+use modio::types::id::{GameId, ModId};
+use modiom::conflict::Ownership;
+use modiom::extract::{self, ExtractOptions};
+use modiom::loadorder::{self, Entry};
+use modiom::local;
+use modiom::lockfile::{self, LockedMod, Lockfile};
+use modiom::manifest::{
+    self, Extract, Game, Identifier, LoadOrder, ModDependencies, ModDependency,
+};
+use modiom::mirror;
+use modiom::policy::Policy;
+use modiom::scan;
+use modiom::state::{self, InstalledFile, InstalledMod, State};
+use modiom::utils::TempDir;
+use modiom::version::VersionSpec;
 use tokio::runtime::Runtime;
 
 use crate::command_prelude::*;
+use crate::plan::{Action, Plan};
+use crate::resolve::{Resolution, Resolver};
+use crate::summary::Summary;
 
 pub fn cli() -> Command {
-    Command::new("install").arg_manifest_path()
+    Command::new("install")
+        .about("Install the mods of Modio.toml")
+        .arg(
+            opt(
+                "offline",
+                "Install the mods locked in Modio.lock, or listed in the mirror index, from the download cache.",
+            )
+            .action(ArgAction::SetTrue),
+        )
+        .arg(
+            opt(
+                "dry-run",
+                "Print the mods that would be downloaded, installed or removed without changing anything.",
+            )
+            .action(ArgAction::SetTrue),
+        )
+        .arg(
+            opt("profile", "Install the mods of the profile defined in Modio.toml.")
+                .value_name("NAME"),
+        )
+        .arg(
+            opt("features", "Comma separated list of optional mod groups to install.")
+                .short('F')
+                .value_name("FEATURES")
+                .value_delimiter(',')
+                .action(ArgAction::Append),
+        )
+        .arg(
+            opt("all-features", "Install the mods of all optional mod groups.")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("features"),
+        )
+        .arg(
+            opt(
+                "prune",
+                "Remove the mods of a previous install that are no longer selected.",
+            )
+            .action(ArgAction::SetTrue),
+        )
+        .arg(
+            opt(
+                "strict",
+                "Fail if mods with the same priority install the same files.",
+            )
+            .action(ArgAction::SetTrue),
+        )
+        .arg_allow_unscanned()
+        .arg_keep_going()
+        .arg_jobs()
+        .arg_manifest_path()
 }
 
 pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
     let path = args.root_manifest(config)?;
     let manifest = manifest::read(&path)?;
+    let mods = args.selected_mods(&manifest)?;
+    let policy = config.policy().for_manifest(&manifest);
+    let game = manifest.game;
+    let load_order = manifest.load_order.as_ref();
+    let root = path.parent().unwrap_or_else(|| Path::new("."));
+    let lock_path = lockfile::path_for(&path);
+    let state_path = state::path_for(root);
+    let dry_run = args.get_flag("dry-run");
+    let strict = args.get_flag("strict");
+    let prune = args.get_flag("prune");
 
-    match manifest.mods {
-        Some(ref mods) if !mods.is_empty() => {}
-        _ => return Err("no mods defined".into()),
+    let previous = lockfile::read(&lock_path)?;
+    let state = state::read(&state_path)?;
+
+    if args.get_flag("offline") {
+        // Without a lockfile the modfiles are looked up in the index of the mirror.
+        let (previous, source) = match previous {
+            Some(previous) => (previous, None),
+            None => {
+                let source = mirror_lock(config, &game, &mods, &lock_path)?;
+                (Lockfile::default(), Some(source))
+            }
+        };
+        let installer = Installer {
+            config,
+            root,
+            previous: &previous,
+            state: &state,
+            policy: &policy,
+            prune,
+        };
+        let source = source.as_ref().unwrap_or(&previous);
+        return install_offline(
+            &installer, &mods, source, &lock_path, load_order, dry_run, strict,
+        );
     }
+    let previous = previous.unwrap_or_default();
 
     let rt = Runtime::new()?;
-    let modio = client(config)?;
-
-    let tasks = async {
-        let game_id = match manifest.game.id {
-            Identifier::Id(id) => GameId::new(id),
-            Identifier::NameId(ref id) => {
-                let filter = NameId::eq(id);
-                let first = modio.games().search(filter).first().await?;
-                if let Some(game) = first {
-                    game.id
-                } else {
-                    return Err(format!("no matching game named `{id}` found").into());
-                }
+    let http = http_client()?;
+    let jobs = args.jobs(config);
+    let keep_going = args.get_flag("keep-going");
+    let allow_unscanned = args.get_flag("allow-unscanned");
+
+    // Mods with a local source are installed without mod.io.
+    let packed = dry_run.then(|| TempDir::new("modiom-local")).transpose()?;
+    let local = rt.block_on(local_mods(root, &mods, &policy, packed.as_ref()))?;
+    let remote = manifest::remote_mods(&mods);
+    let (game_id, resolution) = if remote.is_empty() {
+        (previous.game, Resolution::default())
+    } else {
+        let modio = client(config)?;
+        let resolver = Resolver {
+            config,
+            modio: &modio,
+            jobs,
+            keep_going,
+            policy: Some(&policy),
+            dependency_graph: load_order.is_some(),
+        };
+        rt.block_on(async {
+            let game_id = resolver.game_id(&game.id).await?;
+            let resolution = resolver.resolve(game_id, &game, &remote).await?;
+            Ok::<_, Box<dyn std::error::Error>>((Some(game_id), resolution))
+        })?
+    };
+
+    let mut summary = Summary::default();
+    let mut failed = HashSet::new();
+    for (name, e) in resolution.failed {
+        summary.failed(&name, e.to_string());
+        failed.insert(name);
+    }
+    let installer = Installer {
+        config,
+        root,
+        previous: &previous,
+        state: &state,
+        policy: &policy,
+        prune,
+    };
+    let mut targets = resolution
+        .mods
+        .iter()
+        .map(|r| {
+            let mut locked = LockedMod::new(&r.name, &r.mod_, &r.file);
+            locked.dependency_of = r.dependency_of.clone();
+            locked.dependencies = r.dependencies.clone();
+            let mut target = installer.target(&mods, locked);
+            target.file = Some(&r.file);
+            target
+        })
+        .collect::<Vec<_>>();
+    for (locked, archive) in local {
+        let mut target = installer.target(&mods, locked);
+        target.archive = Some(archive);
+        targets.push(target);
+    }
+
+    let removed = installer.removed(&targets, &failed);
+
+    if dry_run {
+        // Only the file lists of installed and cached modfiles are known without downloading.
+        let errors = check_scans(&targets, allow_unscanned, |t| t.current.is_none());
+        drop_failed(&mut targets, errors, keep_going, &mut summary, &mut failed)?;
+        for t in targets.iter_mut().filter(|t| t.current.is_none()) {
+            if t.archive.is_none() {
+                t.archive = archive_path(config, &t.locked);
             }
+        }
+        let errors = installer.list_files(&mut targets);
+        drop_failed(&mut targets, errors, keep_going, &mut summary, &mut failed)?;
+        let ownership = ownership(&targets);
+        report_conflicts(&ownership, strict)?;
+        installer.assign(&mut targets, &ownership);
+        let errors = check_scans(&targets, allow_unscanned, |t| {
+            t.current.is_some() && !t.up_to_date
+        });
+        drop_failed(&mut targets, errors, keep_going, &mut summary, &mut failed)?;
+
+        let mut plan = Plan::default();
+        for t in &targets {
+            let action = if t.up_to_date {
+                Action::UpToDate
+            } else if t.locked.local.is_some() {
+                Action::Local
+            } else if t.archive.is_some() {
+                Action::Cached
+            } else {
+                Action::Download
+            };
+            plan_step(&mut plan, &t.locked, action);
+        }
+        for locked in removed {
+            plan_step(&mut plan, locked, Action::Remove);
+        }
+        plan.print();
+        return if keep_going && summary.failures() > 0 {
+            summary.print();
+            summary.into_result()
+        } else {
+            Ok(())
         };
-        let mut tasks = vec![];
-        for (_, m) in manifest.mods.unwrap_or_default() {
-            let filter;
-            let not_found: Box<dyn std::error::Error> = match m.id() {
-                Identifier::Id(id) => {
-                    filter = Id::eq(id);
-                    format!("mod with id `{id}` not found").into()
-                }
-                Identifier::NameId(id) => {
-                    filter = NameId::eq(id);
-                    format!("mod with name-id `{id}` not found").into()
+    }
+
+    // The file lists of mods installed with the same modfile and options are read from the
+    // install state, all other archives are needed to detect conflicts.
+    let errors = rt.block_on(fetch_archives(
+        config,
+        &http,
+        &mut targets,
+        jobs,
+        allow_unscanned,
+        |t| t.current.is_none(),
+    ));
+    drop_failed(&mut targets, errors, keep_going, &mut summary, &mut failed)?;
+    let errors = installer.list_files(&mut targets);
+    drop_failed(&mut targets, errors, keep_going, &mut summary, &mut failed)?;
+
+    let ownership = ownership(&targets);
+    report_conflicts(&ownership, strict)?;
+    installer.assign(&mut targets, &ownership);
+
+    // Installed mods whose files changed owner are reinstalled from the archive.
+    let errors = rt.block_on(fetch_archives(
+        config,
+        &http,
+        &mut targets,
+        jobs,
+        allow_unscanned,
+        |t| !t.up_to_date,
+    ));
+    drop_failed(&mut targets, errors, keep_going, &mut summary, &mut failed)?;
+
+    let installed = installer.apply(&targets, &removed, &mut failed, keep_going, &mut summary)?;
+    let (lock, state) = installer.record(&targets, installed, &failed, game_id);
+    lockfile::write(&lock_path, &lock)?;
+    state::write(&state_path, &state)?;
+    if let Some(load_order) = load_order {
+        write_load_order(root, load_order, &mods, &lock.mods)?;
+    }
+
+    if keep_going {
+        summary.print();
+        summary.into_result()
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the extract options of a mod of the manifest. Dependencies are always copied.
+fn extract_options<'a>(mods: &'a ModDependencies, locked: &LockedMod) -> Option<&'a Extract> {
+    if locked.dependency_of.is_some() {
+        return None;
+    }
+    mods.get(&locked.name).and_then(ModDependency::extract)
+}
+
+/// Write the installed mods in the order of their dependencies to the load order file.
+fn write_load_order(
+    root: &Path,
+    load_order: &LoadOrder,
+    mods: &ModDependencies,
+    locked: &[LockedMod],
+) -> Result<()> {
+    let entries = loadorder::sort(locked)?
+        .into_iter()
+        .map(|l| {
+            let path = match extract_options(mods, l) {
+                Some(extract) => {
+                    let opts = ExtractOptions::new(extract, &l.name, &l.name_id, l.id)?;
+                    Some(extract::to_slash(&opts.dir)).filter(|p| !p.is_empty())
                 }
+                None => Some(l.filename.clone()),
+            };
+            Ok(Entry {
+                name: &l.name,
+                name_id: &l.name_id,
+                id: l.id,
+                version: l.version.as_deref(),
+                filename: &l.filename,
+                path: path.unwrap_or_else(|| ".".to_owned()),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let path = root.join(&load_order.file);
+    println!("Writing load order: {}", path.display());
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, loadorder::render(load_order, &entries)?)?;
+    Ok(())
+}
+
+fn plan_step(plan: &mut Plan, locked: &LockedMod, action: Action) {
+    plan.add(
+        &locked.name,
+        locked.version.clone(),
+        &locked.filename,
+        locked.size,
+        action,
+    );
+}
+
+/// Returns the lock entries and archives of the mods with a local source. Local mods are
+/// tracked by their name in the manifest, only the name-id entries of the allow and block lists
+/// apply to them. Directories are packed into the temporary directory `packed` for a dry run.
+async fn local_mods(
+    root: &Path,
+    mods: &ModDependencies,
+    policy: &Policy,
+    packed: Option<&TempDir>,
+) -> Result<Vec<(LockedMod, PathBuf)>> {
+    let out = match packed {
+        Some(dir) => dir.path().to_owned(),
+        None => local::pack_dir(root),
+    };
+    let mut local_mods = vec![];
+    for (name, dep) in mods {
+        let Some(source) = dep.local() else {
+            continue;
+        };
+        policy.check_listed(None, name)?;
+        let archive = local::archive(root, name, source, &out)?;
+        let locked = local::lock(name, source, &archive).await?;
+        local_mods.push((locked, archive));
+    }
+    Ok(local_mods)
+}
+
+/// Download the missing archives of the targets selected by `needed`. Returns the mods that
+/// failed to download.
+async fn fetch_archives<F>(
+    config: &Config,
+    http: &reqwest::Client,
+    targets: &mut [Target<'_>],
+    jobs: usize,
+    allow_unscanned: bool,
+    needed: F,
+) -> Vec<(String, Box<dyn StdError>)>
+where
+    F: Fn(&Target<'_>) -> bool,
+{
+    let tasks = targets
+        .iter_mut()
+        .filter(|t| t.archive.is_none() && needed(t))
+        .filter_map(|t| Some((t.file?, t)))
+        .map(|(file, target)| async move {
+            let res = fetch_modfile(config, http, file, allow_unscanned).await;
+            (target, res)
+        });
+    let mut st = stream::iter(tasks).buffer_unordered(jobs);
+
+    let mut errors = vec![];
+    while let Some((target, res)) = st.next().await {
+        match res {
+            Ok(archive) => target.archive = Some(archive),
+            Err(e) => errors.push((target.locked.name.clone(), e)),
+        }
+    }
+    errors
+}
+
+/// Check the virus scan of the modfiles `fetch_archives` would download for the targets
+/// selected by `needed`.
+fn check_scans<F>(
+    targets: &[Target<'_>],
+    allow_unscanned: bool,
+    needed: F,
+) -> Vec<(String, Box<dyn StdError>)>
+where
+    F: Fn(&Target<'_>) -> bool,
+{
+    targets
+        .iter()
+        .filter(|t| needed(t))
+        .filter_map(|t| Some((t, scan::check(t.file?, allow_unscanned).err()?)))
+        .map(|(t, e)| (t.locked.name.clone(), e.into()))
+        .collect()
+}
+
+/// Remove the failed mods from the targets. Without `--keep-going` the first error is returned.
+fn drop_failed(
+    targets: &mut Vec<Target<'_>>,
+    errors: Vec<(String, Box<dyn StdError>)>,
+    keep_going: bool,
+    summary: &mut Summary,
+    failed: &mut HashSet<String>,
+) -> Result<()> {
+    for (name, e) in errors {
+        if !keep_going {
+            return Err(e);
+        }
+        summary.failed(&name, e.to_string());
+        targets.retain(|t| t.locked.name != name);
+        failed.insert(name);
+    }
+    Ok(())
+}
+
+/// Assign each file to the mod with the highest priority.
+fn ownership(targets: &[Target<'_>]) -> Ownership {
+    Ownership::new(targets.iter().map(|t| {
+        let paths = t.paths.iter().map(String::as_str);
+        (t.locked.name.as_str(), t.priority, paths)
+    }))
+}
+
+/// Print the files installed by more than one mod. Conflicts between mods with the same
+/// priority are an error in strict mode.
+fn report_conflicts(ownership: &Ownership, strict: bool) -> Result<()> {
+    let mut unresolved = 0;
+    for c in &ownership.conflicts {
+        let more = match c.paths.len() {
+            1 => String::new(),
+            n => format!(" (and {} more)", n - 1),
+        };
+        if c.resolved {
+            println!(
+                "Overriding: `{}`{more} of `{}` with `{}`",
+                c.paths[0], c.loser, c.winner
+            );
+        } else {
+            unresolved += 1;
+            eprintln!(
+                "warning: mods `{}` and `{}` both install `{}`{more}, `{}` takes precedence",
+                c.loser, c.winner, c.paths[0], c.winner
+            );
+        }
+    }
+    if strict && unresolved > 0 {
+        return Err(Error::usage(format!(
+            "{unresolved} unresolved file conflict(s), set a `priority` for the conflicting mods in Modio.toml"
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// A mod to install.
+struct Target<'a> {
+    locked: LockedMod,
+    /// The modfile to download. `None` for offline installs.
+    file: Option<&'a File>,
+    extract: Option<&'a Extract>,
+    priority: i32,
+    /// The installed mod if the same modfile is installed with the same options.
+    current: Option<InstalledMod>,
+    /// The archive in the download cache.
+    archive: Option<PathBuf>,
+    /// All files of the modfile.
+    paths: Vec<String>,
+    /// The files installed by this mod.
+    owned: HashSet<String>,
+    /// The files installed by mods with a higher priority.
+    shadowed: Vec<String>,
+    up_to_date: bool,
+}
+
+struct Installer<'a> {
+    config: &'a Config,
+    root: &'a Path,
+    /// The lockfile of the previous install.
+    previous: &'a Lockfile,
+    /// The installed files of the previous install.
+    state: &'a State,
+    /// The content policy of the config and the manifest.
+    policy: &'a Policy,
+    /// Remove the mods of the previous install that aren't selected anymore. They are kept
+    /// installed and locked otherwise.
+    prune: bool,
+}
+
+impl<'a> Installer<'a> {
+    /// Returns the installed files of the mod from the state file or the lockfile.
+    fn installed(&self, name: &str) -> Option<InstalledMod> {
+        if let Some(installed) = self.state.get(name) {
+            return Some(installed.clone());
+        }
+        self.previous.get(name).map(InstalledMod::from_locked)
+    }
+
+    /// Dependencies are installed with the priority 0.
+    fn target<'m>(&self, mods: &'m ModDependencies, locked: LockedMod) -> Target<'m> {
+        let extract = extract_options(mods, &locked);
+        let priority = match locked.dependency_of {
+            Some(_) => 0,
+            None => mods.get(&locked.name).map_or(0, ModDependency::priority),
+        };
+        let current = Some(&locked)
+            .filter(|l| self.previous.get(&l.name) == Some(l))
+            .and_then(|l| self.installed(&l.name))
+            .filter(|m| m.extract.as_ref() == extract);
+        Target {
+            locked,
+            file: None,
+            extract,
+            priority,
+            current,
+            archive: None,
+            paths: vec![],
+            owned: HashSet::new(),
+            shadowed: vec![],
+            up_to_date: false,
+        }
+    }
+
+    /// Returns the previously installed mods that are no longer part of the manifest, the
+    /// profile or the selected features.
+    fn unselected(&self, targets: &[Target<'_>], failed: &HashSet<String>) -> Vec<&'a LockedMod> {
+        let names = targets
+            .iter()
+            .map(|t| t.locked.name.as_str())
+            .collect::<HashSet<_>>();
+        self.previous
+            .mods
+            .iter()
+            .filter(|l| !failed.contains(&l.name) && !names.contains(l.name.as_str()))
+            .collect()
+    }
+
+    /// Returns the unselected mods to remove with `--prune`.
+    fn removed(&self, targets: &[Target<'_>], failed: &HashSet<String>) -> Vec<&'a LockedMod> {
+        if self.prune {
+            self.unselected(targets, failed)
+        } else {
+            vec![]
+        }
+    }
+
+    /// Returns the unselected mods that stay installed without `--prune`.
+    fn kept(&self, targets: &[Target<'_>], failed: &HashSet<String>) -> Vec<&'a LockedMod> {
+        if self.prune {
+            vec![]
+        } else {
+            self.unselected(targets, failed)
+        }
+    }
+
+    /// Returns the lockfile and the install state after installing the targets. The previously
+    /// installed modfiles of failed and kept mods are still in place.
+    fn record(
+        &self,
+        targets: &[Target<'_>],
+        mut installed: Vec<InstalledMod>,
+        failed: &HashSet<String>,
+        game: Option<GameId>,
+    ) -> (Lockfile, State) {
+        let mut locked = targets
+            .iter()
+            .filter(|t| !failed.contains(&t.locked.name))
+            .map(|t| t.locked.clone())
+            .collect::<Vec<_>>();
+        for name in failed {
+            locked.extend(self.previous.get(name).cloned());
+            installed.extend(self.installed(name));
+        }
+        for l in self.kept(targets, failed) {
+            locked.push(l.clone());
+            installed.extend(self.installed(&l.name));
+        }
+        locked.sort_by(|a, b| a.name.cmp(&b.name));
+        installed.sort_by(|a, b| a.name.cmp(&b.name));
+        (Lockfile { game, mods: locked }, State { mods: installed })
+    }
+
+    /// Read the file lists of the targets from the install state or the archives. Modfiles
+    /// without either have no known files. Returns the mods whose archive can't be read.
+    fn list_files(&self, targets: &mut [Target<'_>]) -> Vec<(String, Box<dyn StdError>)> {
+        let mut errors = vec![];
+        for t in targets {
+            let paths = match (&t.current, &t.archive, t.extract) {
+                (Some(current), _, _) => Ok(current.paths().map(String::from).collect()),
+                (None, Some(archive), Some(extract)) => self
+                    .options(&t.locked, extract)
+                    .and_then(|opts| extract::list(archive, &opts)),
+                (None, Some(_), None) => Ok(vec![t.locked.filename.clone()]),
+                (None, None, _) => Ok(vec![]),
             };
-            tasks.push(async {
-                let mod_ = modio.game(game_id).mods().search(filter).first().await?;
-                match mod_ {
-                    None => Err(not_found),
-                    Some(m) if m.modfile.is_none() => {
-                        Err(format!("mod `{}` has no primary file", m.name_id).into())
-                    }
-                    Some(m) => {
-                        let file = m.modfile.unwrap();
-                        println!("Downloading: {}", file.download.binary_url);
-                        let out = Path::new(&file.filename).to_path_buf();
-                        modio.download(file).await?.save_to_file(out).await?;
-                        Ok(())
-                    }
+            match paths {
+                Ok(paths) => t.paths = paths,
+                Err(e) => errors.push((t.locked.name.clone(), e)),
+            }
+        }
+        errors
+    }
+
+    /// Split the files of each target into the files it installs and the shadowed files. Mods
+    /// are up to date if they still install the same files.
+    fn assign(&self, targets: &mut [Target<'_>], ownership: &Ownership) {
+        for (idx, t) in targets.iter_mut().enumerate() {
+            let (owned, shadowed) = t
+                .paths
+                .iter()
+                .cloned()
+                .partition::<Vec<_>, _>(|p| ownership.owner(p) == Some(idx));
+            t.owned = owned.into_iter().collect();
+            t.shadowed = shadowed;
+            t.up_to_date = t.current.as_ref().is_some_and(|current| {
+                current.files.len() == t.owned.len()
+                    && current.files.iter().all(|f| t.owned.contains(&f.path))
+                    && current.exists(self.root)
+            });
+        }
+    }
+
+    fn options(&self, locked: &LockedMod, extract: &Extract) -> Result<ExtractOptions> {
+        ExtractOptions::new(extract, &locked.name, &locked.name_id, locked.id)
+    }
+
+    /// Remove the files of the removed and outdated mods and install the outdated mods.
+    fn apply(
+        &self,
+        targets: &[Target<'_>],
+        removed: &[&LockedMod],
+        failed: &mut HashSet<String>,
+        keep_going: bool,
+        summary: &mut Summary,
+    ) -> Result<Vec<InstalledMod>> {
+        // Files of up-to-date, failed and kept mods stay in place.
+        let kept = failed
+            .iter()
+            .map(String::as_str)
+            .chain(self.kept(targets, failed).iter().map(|l| l.name.as_str()))
+            .filter_map(|name| self.installed(name))
+            .collect::<Vec<_>>();
+        let mut keep = kept
+            .iter()
+            .flat_map(|m| m.files.iter().map(|f| f.path.as_str()))
+            .collect::<HashSet<_>>();
+        for t in targets.iter().filter(|t| t.up_to_date) {
+            keep.extend(t.owned.iter().map(String::as_str));
+        }
+
+        // Remove first, a mod may install files with the same paths.
+        for l in removed {
+            if let Some(installed) = self.installed(&l.name) {
+                println!("Removing: {}", l.name);
+                installed.remove_files(self.root, |p| keep.contains(p))?;
+                summary.succeeded(&l.name, format!("removed {}", l.filename));
+            }
+        }
+        for t in targets.iter().filter(|t| !t.up_to_date) {
+            if let Some(previous) = self.installed(&t.locked.name) {
+                previous.remove_files(self.root, |p| keep.contains(p))?;
+            }
+        }
+
+        let mut installed = vec![];
+        for t in targets {
+            let name = &t.locked.name;
+            if t.up_to_date {
+                let mut current = t.current.clone().expect("up-to-date mod is installed");
+                current.shadowed = t.shadowed.clone();
+                summary.skipped(name, format!("{} is up to date", t.locked.filename));
+                installed.push(current);
+                continue;
+            }
+            match self.install(t) {
+                Ok(inst) => {
+                    summary.succeeded(name, format!("installed {}", t.locked.filename));
+                    installed.push(inst);
+                }
+                Err(e) if keep_going => {
+                    summary.failed(name, e.to_string());
+                    failed.insert(name.clone());
                 }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(installed)
+    }
+
+    /// Install the files of the archive owned by the mod.
+    fn install(&self, t: &Target<'_>) -> Result<InstalledMod> {
+        let locked = &t.locked;
+        let archive = t.archive.as_deref().expect("archive of outdated mod");
+        println!("Installing: {}", locked.filename);
+        let files = match t.extract {
+            Some(extract) => {
+                let opts = self.options(locked, extract)?;
+                let dir = self.root.join(&opts.dir);
+                println!("Extracting: {} to {}", locked.filename, dir.display());
+                extract::extract(archive, self.root, &opts, |p| t.owned.contains(p))?
+            }
+            None if t.owned.contains(&locked.filename) => {
+                std::fs::copy(archive, self.root.join(&locked.filename))?;
+                vec![InstalledFile {
+                    path: locked.filename.clone(),
+                    size: locked.size,
+                    md5: locked.md5.clone(),
+                }]
+            }
+            None => vec![],
+        };
+        Ok(InstalledMod {
+            name: locked.name.clone(),
+            id: locked.id,
+            file: locked.file,
+            extract: t.extract.cloned(),
+            files,
+            shadowed: t.shadowed.clone(),
+        })
+    }
+}
+
+fn install_offline(
+    installer: &Installer<'_>,
+    mods: &ModDependencies,
+    lock: &Lockfile,
+    lock_path: &Path,
+    load_order: Option<&LoadOrder>,
+    dry_run: bool,
+    strict: bool,
+) -> CliResult {
+    let (config, policy) = (installer.config, installer.policy);
+    let rt = Runtime::new()?;
+    let packed = dry_run.then(|| TempDir::new("modiom-local")).transpose()?;
+    let local = rt.block_on(local_mods(installer.root, mods, policy, packed.as_ref()))?;
+
+    let mut archives = vec![];
+    let mut missing = vec![];
+    for (name, dep) in mods.iter().filter(|(_, dep)| dep.local().is_none()) {
+        let locked = match lock.get(name) {
+            Some(locked) => locked,
+            None => {
+                missing.push(format!("{name}: not locked in Modio.lock"));
+                continue;
+            }
+        };
+        let spec = dep.version().map(|v| VersionSpec::parse(v)).transpose()?;
+        let outdated = !dep.id().is_some_and(|id| locked.matches(id))
+            || locked.local.is_some()
+            || dep
+                .file()
+                .is_some_and(|f| locked.file.map(|id| id.get()) != Some(u64::from(f)))
+            || spec.is_some_and(|spec| !spec.matches(locked.version.as_deref()));
+        if outdated {
+            missing.push(format!("{name}: Modio.lock is out of date"));
+            continue;
+        }
+        archives.push(locked);
+    }
+    archives.extend(lock.dependencies_of(&archives));
+    // Mods with a local source have no maturity flags.
+    for locked in &archives {
+        let res = policy
+            .check_listed(locked.id, &locked.name_id)
+            .and_then(|()| match locked.local {
+                Some(_) => Ok(()),
+                None => policy.check_maturity(&locked.name_id, locked.maturity()),
             });
+        if let Err(e) = res {
+            return Err(match &locked.dependency_of {
+                Some(parent) => Error::new(e.kind(), format!("{e}, required by `{parent}`")),
+                None => e,
+            }
+            .into());
         }
-        tasks
-            .into_iter()
-            .collect::<stream::FuturesUnordered<_>>()
-            .try_collect::<Vec<()>>()
-            .await
-    };
+    }
+
+    let mut targets = vec![];
+    for locked in archives {
+        // A dry run doesn't mark the archives as used or copy them from the mirror.
+        let archive = if dry_run {
+            archive_path(config, locked)
+        } else {
+            rt.block_on(cached_archive(config, locked))?
+        };
+        match archive {
+            Some(archive) => {
+                let mut target = installer.target(mods, locked.clone());
+                target.archive = Some(archive);
+                targets.push(target);
+            }
+            None => missing.push(format!(
+                "{}: {} (file: {}, md5: {})",
+                locked.name,
+                locked.filename,
+                locked.file.map(|id| id.to_string()).unwrap_or_default(),
+                locked.md5
+            )),
+        }
+    }
+    for (locked, archive) in local {
+        let mut target = installer.target(mods, locked);
+        target.archive = Some(archive);
+        targets.push(target);
+    }
+
+    if !missing.is_empty() {
+        let mirror = match config.mirror() {
+            Some(mirror) => format!(" or the mirror `{}`", mirror.root().display()),
+            None => String::new(),
+        };
+        let mut msg = format!(
+            "cannot install offline, {} archive(s) not available in the download cache `{}`{mirror}:",
+            missing.len(),
+            config.cache().root().display()
+        );
+        for m in missing {
+            msg.push_str("\n  ");
+            msg.push_str(&m);
+        }
+        return Err(Error::not_found(msg).into());
+    }
+
+    if let Some((_, e)) = installer.list_files(&mut targets).into_iter().next() {
+        return Err(e.into());
+    }
+    let ownership = ownership(&targets);
+    report_conflicts(&ownership, strict)?;
+    installer.assign(&mut targets, &ownership);
 
-    rt.block_on(tasks)?;
+    let mut failed = HashSet::new();
+    let removed = installer.removed(&targets, &failed);
+    if dry_run {
+        let mut plan = Plan::default();
+        for t in &targets {
+            let action = if t.up_to_date {
+                Action::UpToDate
+            } else if t.locked.local.is_some() {
+                Action::Local
+            } else {
+                Action::Cached
+            };
+            plan_step(&mut plan, &t.locked, action);
+        }
+        for locked in removed {
+            plan_step(&mut plan, locked, Action::Remove);
+        }
+        plan.print();
+        return Ok(());
+    }
 
+    let mut summary = Summary::default();
+    let installed = installer.apply(&targets, &removed, &mut failed, false, &mut summary)?;
+    let (lock, state) = installer.record(&targets, installed, &failed, lock.game);
+    lockfile::write(lock_path, &lock)?;
+    state::write(&state::path_for(installer.root), &state)?;
+    if let Some(load_order) = load_order {
+        write_load_order(installer.root, load_order, mods, &lock.mods)?;
+    }
     Ok(())
 }
+
+/// Returns the lock entries of the mods and the dependencies they pull in from the index of the
+/// mirror. The mirrored modfile with the highest id that matches the version or file of the mod
+/// is selected.
+fn mirror_lock(
+    config: &Config,
+    game: &Game,
+    mods: &ModDependencies,
+    lock_path: &Path,
+) -> Result<Lockfile> {
+    let Some(mirror) = config.mirror() else {
+        return Err(Error::not_found(format!(
+            "`{}` not found, installing offline requires a lockfile or a mirror",
+            lock_path.display()
+        ))
+        .into());
+    };
+    let index = mirror::read(mirror.root())?;
+    let game_id = match game.id {
+        Identifier::Id(id) => GameId::new_checked(id),
+        Identifier::NameId(_) => None,
+    };
+    let entries = index
+        .mods
+        .iter()
+        .filter(|m| game_id.is_none_or(|id| m.game == id))
+        .collect::<Vec<_>>();
+    let latest = |id: ModId| entries.iter().filter(|m| m.id == id).max_by_key(|m| m.file);
+
+    let mut lock = Lockfile {
+        game: game_id,
+        mods: vec![],
+    };
+    let mut missing = vec![];
+    let mut pulled_in = VecDeque::new();
+    for (name, dep) in mods {
+        // Mods with a local source aren't mirrored.
+        let Some(id) = dep.id() else {
+            continue;
+        };
+        let spec = dep.version().map(|v| VersionSpec::parse(v)).transpose()?;
+        let found = entries
+            .iter()
+            .filter(|m| match id {
+                Identifier::Id(id) => m.id.get() == *id,
+                Identifier::NameId(name_id) => m.name_id == *name_id,
+            })
+            .filter(|m| dep.file().is_none_or(|f| m.file.get() == u64::from(f)))
+            .filter(|m| {
+                spec.as_ref()
+                    .is_none_or(|s| s.matches(m.version.as_deref()))
+            })
+            .max_by_key(|m| m.file);
+        let Some(m) = found else {
+            missing.push(name.clone());
+            continue;
+        };
+        if dep
+            .with_dependencies()
+            .or(game.with_dependencies)
+            .unwrap_or(false)
+        {
+            pulled_in.extend(m.dependencies.iter().map(|id| (name.clone(), *id)));
+        }
+        lock.mods.push(m.locked(name));
+    }
+
+    // The first mod that pulls in a dependency is recorded, like an online install does.
+    let mut seen = lock
+        .mods
+        .iter()
+        .filter_map(|l| l.id)
+        .collect::<HashSet<_>>();
+    while let Some((parent, id)) = pulled_in.pop_front() {
+        if !seen.insert(id) {
+            continue;
+        }
+        let Some(m) = latest(id) else {
+            missing.push(format!("dependency `{id}` of `{parent}`"));
+            continue;
+        };
+        pulled_in.extend(m.dependencies.iter().map(|id| (m.name_id.clone(), *id)));
+        let mut locked = m.locked(&m.name_id);
+        locked.dependency_of = Some(parent);
+        lock.mods.push(locked);
+    }
+
+    if !missing.is_empty() {
+        let mut msg = format!(
+            "cannot install offline without `{}`, {} mod(s) not found in the mirror index `{}`:",
+            lock_path.display(),
+            missing.len(),
+            mirror::path_for(mirror.root()).display()
+        );
+        for m in missing {
+            msg.push_str("\n  ");
+            msg.push_str(&m);
+        }
+        return Err(Error::not_found(msg).into());
+    }
+    lock.game = lock.game.or_else(|| entries.first().map(|m| m.game));
+    Ok(lock)
+}
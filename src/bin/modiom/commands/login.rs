@@ -43,7 +43,7 @@ pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
                 let code = prompt("Enter security code: ")?;
                 match rt.block_on(m.auth().security_code(&code)) {
                     Ok(token) => break token,
-                    Err(err) => println!("{err}"),
+                    Err(err) => eprintln!("error: {err}"),
                 };
             }
         }
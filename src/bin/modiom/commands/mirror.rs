@@ -0,0 +1,305 @@
+use std::fs;
+
+use futures::{stream, StreamExt, TryStreamExt};
+use tokio::runtime::Runtime;
+
+use modio::filter::custom_filter;
+use modio::filter::prelude::*;
+use modio::types::files::File;
+use modio::types::id::{GameId, ModId};
+use modio::types::mods::Mod;
+use modio::Modio;
+use modiom::cache::Cache;
+use modiom::manifest;
+use modiom::mirror::{self, MirroredMod};
+use modiom::scan;
+
+use crate::command_prelude::*;
+use crate::commands::expr;
+use crate::plan::{Action, Plan};
+use crate::resolve::Resolver;
+use crate::summary::Summary;
+
+pub fn cli() -> Command {
+    Command::new("mirror")
+        .about("Copy the modfiles of the manifest or a mod search into a mirror directory")
+        .arg(
+            Arg::new("dest")
+                .help("The mirror directory")
+                .value_name("DEST")
+                .required(true)
+                .value_parser(ValueParser::path_buf()),
+        )
+        .arg(
+            opt(
+                "game-id",
+                "Mirror the mods of the game matching the search options instead of Modio.toml.",
+            )
+            .value_name("ID")
+            .value_parser(value_parser!(GameId))
+            .conflicts_with_all([
+                "manifest-path",
+                "profile",
+                "features",
+                "all-features",
+            ]),
+        )
+        .arg(
+            opt(
+                "expr",
+                "Filter expression of the mod search, see `modiom search`.",
+            )
+            .num_args(1)
+            .value_name("EXPR")
+            .action(ArgAction::Append)
+            .requires("game-id"),
+        )
+        .arg(
+            opt("fulltext", "Full-text search of the mod search.")
+                .value_name("TEXT")
+                .requires("game-id"),
+        )
+        .arg(
+            opt(
+                "profile",
+                "Mirror the mods of the profile defined in Modio.toml.",
+            )
+            .value_name("NAME"),
+        )
+        .arg(
+            opt(
+                "features",
+                "Comma separated list of optional mod groups to mirror.",
+            )
+            .short('F')
+            .value_name("FEATURES")
+            .value_delimiter(',')
+            .action(ArgAction::Append),
+        )
+        .arg(
+            opt(
+                "all-features",
+                "Mirror the mods of all optional mod groups.",
+            )
+            .action(ArgAction::SetTrue)
+            .conflicts_with("features"),
+        )
+        .arg(
+            opt(
+                "dry-run",
+                "Print the modfiles that would be copied into the mirror without copying them.",
+            )
+            .action(ArgAction::SetTrue),
+        )
+        .arg_allow_unscanned()
+        .arg_keep_going()
+        .arg_jobs()
+        .arg_manifest_path()
+}
+
+/// A mod to mirror with its selected modfile.
+struct Target {
+    name: String,
+    mod_: Mod,
+    file: File,
+    dependencies: Vec<ModId>,
+}
+
+pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
+    let dest = args.get_path("dest").expect("required arg");
+    let keep_going = args.get_flag("keep-going");
+    let allow_unscanned = args.get_flag("allow-unscanned");
+
+    let rt = Runtime::new()?;
+    let modio = client(config)?;
+    let mut summary = Summary::default();
+    let targets = match args.get_one::<GameId>("game-id") {
+        Some(game_id) => rt.block_on(search(config, args, &modio, *game_id, &mut summary))?,
+        None => rt.block_on(resolve(config, args, &modio, keep_going, &mut summary))?,
+    };
+
+    // The mirror uses the layout of the download cache, so `[source] mirror` of the config can
+    // point to it.
+    let store = Cache::new(dest.clone());
+    let cache = config.cache();
+
+    if args.get_flag("dry-run") {
+        let mut plan = Plan::default();
+        for t in targets {
+            if let Err(e) = scan::check(&t.file, allow_unscanned) {
+                summary.skipped(t.name, e.to_string());
+                continue;
+            }
+            let (file_id, md5, filename) = (t.file.id, &t.file.filehash.md5, &t.file.filename);
+            let action = if store.contains(file_id, md5, filename) {
+                Action::UpToDate
+            } else if cache.contains(file_id, md5, filename) {
+                Action::Cached
+            } else {
+                Action::Download
+            };
+            plan.add(
+                t.name,
+                t.file.version,
+                t.file.filename,
+                t.file.filesize,
+                action,
+            );
+        }
+        plan.print();
+        if !summary.is_empty() {
+            summary.print();
+        }
+        return summary.into_result();
+    }
+
+    fs::create_dir_all(dest)?;
+    let mut index = mirror::read(dest)?;
+    let http = http_client()?;
+    let tasks = targets.into_iter().map(|t| {
+        let (store, cache, http) = (&store, &cache, &http);
+        async move {
+            let res = async {
+                scan::check(&t.file, allow_unscanned)?;
+                let (file_id, md5, filename) = (t.file.id, &t.file.filehash.md5, &t.file.filename);
+                if store.contains(file_id, md5, filename) {
+                    return Ok("up to date".to_owned());
+                }
+                fetch_modfile(config, http, &t.file, allow_unscanned).await?;
+                // Copying from the download cache verifies the size and checksum again.
+                store
+                    .import(cache, file_id, filename, t.file.filesize, md5)
+                    .await?
+                    .ok_or_else(|| format!("`{filename}` is missing from the download cache"))?;
+                Ok::<_, Box<dyn std::error::Error>>(format!("mirrored {filename}"))
+            };
+            let res = res.await;
+            (t, res)
+        }
+    });
+    let mut st = stream::iter(tasks).buffer_unordered(args.jobs(config));
+    let mut error = None;
+    while let Some((t, res)) = rt.block_on(st.next()) {
+        match res {
+            Ok(details) => {
+                index.add(MirroredMod::new(&t.mod_, &t.file, t.dependencies)?);
+                summary.succeeded(t.name, details);
+            }
+            Err(e) if keep_going => summary.failed(t.name, e.to_string()),
+            Err(e) => {
+                error = Some(e);
+                break;
+            }
+        }
+    }
+    drop(st);
+    // The index lists the modfiles copied before a failure as well.
+    mirror::write(dest, &index)?;
+    if let Some(e) = error {
+        return Err(e.into());
+    }
+    summary.print();
+    summary.into_result()
+}
+
+/// Returns the primary files of the mods matching the search options.
+async fn search(
+    config: &Config,
+    args: &ArgMatches,
+    modio: &Modio,
+    game_id: GameId,
+    summary: &mut Summary,
+) -> Result<Vec<Target>> {
+    let mut filter = Filter::default();
+    if let Some(vals) = args.get_many::<String>("expr") {
+        for e in vals {
+            let e = expr::parse(e)?;
+            filter = filter.and(custom_filter(e.property, e.op.into(), e.right.into_value()));
+        }
+    }
+    if let Some(ft) = args.get_string("fulltext") {
+        filter = filter.and(Fulltext::eq(ft));
+    }
+    let mods = modio.game(game_id).mods();
+    let list = config
+        .retry()
+        .run(|| mods.search(filter.clone()).collect())
+        .await?;
+
+    let policy = config.policy();
+    let mut targets = vec![];
+    for mut m in list {
+        if let Err(e) = policy.check(&m) {
+            summary.skipped(m.name_id, e.to_string());
+            continue;
+        }
+        let Some(file) = m.modfile.take() else {
+            summary.skipped(m.name_id, "no primary file");
+            continue;
+        };
+        targets.push(Target {
+            name: m.name_id.clone(),
+            mod_: m,
+            file,
+            dependencies: vec![],
+        });
+    }
+
+    // The index records the dependencies, so offline installs can pull them in.
+    let retry = config.retry();
+    let tasks = targets
+        .iter_mut()
+        .filter(|t| t.mod_.dependencies)
+        .map(|t| async move {
+            let deps = modio.mod_(game_id, t.mod_.id).dependencies();
+            let list = retry.run(|| deps.clone().list()).await?;
+            t.dependencies = list.into_iter().map(|d| d.mod_id).collect();
+            Ok::<_, Box<dyn std::error::Error>>(())
+        });
+    stream::iter(tasks)
+        .buffer_unordered(args.jobs(config))
+        .try_collect::<Vec<_>>()
+        .await?;
+    Ok(targets)
+}
+
+/// Returns the modfiles `modiom install` would install for the manifest, including dependencies.
+async fn resolve(
+    config: &Config,
+    args: &ArgMatches,
+    modio: &Modio,
+    keep_going: bool,
+    summary: &mut Summary,
+) -> Result<Vec<Target>> {
+    let path = args.root_manifest(config)?;
+    let manifest = manifest::read(&path)?;
+    // Mods with a local source aren't available on mod.io.
+    let mods = manifest::remote_mods(&args.selected_mods(&manifest)?);
+    let policy = config.policy().for_manifest(&manifest);
+
+    let resolver = Resolver {
+        config,
+        modio,
+        jobs: args.jobs(config),
+        keep_going,
+        policy: Some(&policy),
+        // The index records the dependencies of all mods, so offline installs can pull them in.
+        dependency_graph: true,
+    };
+    let game_id = resolver.game_id(&manifest.game.id).await?;
+    let resolution = resolver.resolve(game_id, &manifest.game, &mods).await?;
+    for (name, e) in resolution.failed {
+        summary.failed(name, e.to_string());
+    }
+    let targets = resolution
+        .mods
+        .into_iter()
+        .map(|r| Target {
+            name: r.name,
+            mod_: r.mod_,
+            file: r.file,
+            dependencies: r.dependencies,
+        })
+        .collect();
+    Ok(targets)
+}
@@ -8,7 +8,16 @@ pub fn builtin() -> Vec<Command> {
         subs::cli(),
         download::cli(),
         upload::cli(),
+        init::cli(),
         install::cli(),
+        check::cli(),
+        add::cli(),
+        remove::cli(),
+        tree::cli(),
+        outdated::cli(),
+        verify::cli(),
+        mirror::cli(),
+        cache::cli(),
     ]
 }
 
@@ -20,16 +29,34 @@ pub fn exec(cfg: &Config, args: &ArgMatches) -> CliResult {
         Some(("subscriptions", matches)) => subs::exec(cfg, matches),
         Some(("download", matches)) => download::exec(cfg, matches),
         Some(("upload", matches)) => upload::exec(cfg, matches),
+        Some(("init", matches)) => init::exec(cfg, matches),
         Some(("install", matches)) => install::exec(cfg, matches),
+        Some(("check", matches)) => check::exec(cfg, matches),
+        Some(("add", matches)) => add::exec(cfg, matches),
+        Some(("remove", matches)) => remove::exec(cfg, matches),
+        Some(("tree", matches)) => tree::exec(cfg, matches),
+        Some(("outdated", matches)) => outdated::exec(cfg, matches),
+        Some(("verify", matches)) => verify::exec(cfg, matches),
+        Some(("mirror", matches)) => mirror::exec(cfg, matches),
+        Some(("cache", matches)) => cache::exec(cfg, matches),
         _ => unreachable!(),
     }
 }
 
+mod add;
+mod cache;
+mod check;
 mod download;
 mod expr;
 mod info;
+mod init;
 mod install;
 mod login;
+mod mirror;
+mod outdated;
+mod remove;
 mod search;
 mod subs;
+mod tree;
 mod upload;
+mod verify;
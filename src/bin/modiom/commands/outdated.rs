@@ -0,0 +1,228 @@
+use futures::{stream, StreamExt};
+use prettytable::{format, Table};
+use tokio::runtime::Runtime;
+
+use modio::filter::prelude::*;
+use modio::types::files::File;
+use modio::types::id;
+use modio::types::mods::Mod;
+use modio::user::filters::subscriptions::GameId;
+use modio::Modio;
+use modiom::lockfile;
+use modiom::manifest;
+use modiom::utils::format_date;
+
+use crate::command_prelude::*;
+use crate::commands::subs::subscriptions;
+use crate::resolve::Resolver;
+
+pub fn cli() -> Command {
+    Command::new("outdated")
+        .about("Show mods with a newer primary file")
+        .arg(
+            opt(
+                "subscriptions",
+                "Check the downloaded modfiles of the subscribed mods instead of Modio.lock.",
+            )
+            .action(ArgAction::SetTrue)
+            .conflicts_with("manifest-path"),
+        )
+        .arg(
+            opt("game-id", "Only check the subscriptions of the game.")
+                .value_name("ID")
+                .value_parser(value_parser!(id::GameId))
+                .requires("subscriptions"),
+        )
+        .arg_manifest_path()
+}
+
+/// A mod whose installed modfile isn't the primary file anymore.
+struct Outdated {
+    name: String,
+    current: Option<String>,
+    latest: File,
+    /// The modfile is pinned with the `file` or `version` field of the manifest.
+    pinned: bool,
+}
+
+pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
+    let rt = Runtime::new()?;
+    let modio = client(config)?;
+
+    let outdated = if args.get_flag("subscriptions") {
+        let game_id = args.get_one::<id::GameId>("game-id");
+        let filter = match game_id {
+            Some(game_id) => GameId::eq(game_id),
+            None => Filter::default(),
+        };
+        rt.block_on(outdated_subscriptions(config, &modio, filter))?
+    } else {
+        outdated_manifest(config, args, &rt, &modio)?
+    };
+
+    if outdated.is_empty() {
+        println!("All mods are up to date.");
+        return Ok(());
+    }
+    print(&outdated);
+    match outdated.iter().filter(|o| !o.pinned).count() {
+        0 => Ok(()),
+        n => Err(format!("{n} of {} mods are outdated", outdated.len()).into()),
+    }
+}
+
+/// Compare the modfiles locked in Modio.lock with the primary files.
+fn outdated_manifest(
+    config: &Config,
+    args: &ArgMatches,
+    rt: &Runtime,
+    modio: &Modio,
+) -> Result<Vec<Outdated>> {
+    let path = args.root_manifest(config)?;
+    let manifest = manifest::read(&path)?;
+    let lock_path = lockfile::path_for(&path);
+    let lock = lockfile::read(&lock_path)?.ok_or_else(|| {
+        Error::not_found(format!(
+            "`{}` not found, run `modiom install` first",
+            lock_path.display()
+        ))
+    })?;
+    if lock.mods.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let resolver = Resolver {
+        config,
+        modio,
+        jobs: config.jobs(),
+        keep_going: false,
+        policy: None,
+        dependency_graph: false,
+    };
+    let game_id = match lock.game {
+        Some(game_id) => game_id,
+        None => rt.block_on(resolver.game_id(&manifest.game.id))?,
+    };
+    // Mods installed from a local source aren't compared with mod.io.
+    let locked = lock
+        .mods
+        .iter()
+        .filter(|l| l.local.is_none())
+        .collect::<Vec<_>>();
+    if locked.is_empty() {
+        return Ok(vec![]);
+    }
+    let ids = locked.iter().filter_map(|l| l.id).collect::<Vec<_>>();
+    let mods = modio.game(game_id).mods();
+    let mut found = rt.block_on(
+        config
+            .retry()
+            .run(|| mods.search(Id::_in(ids.clone())).collect()),
+    )?;
+
+    let profiles = manifest
+        .profile
+        .values()
+        .flat_map(|p| p.mods.iter().flatten());
+    let deps = manifest
+        .mods
+        .iter()
+        .flatten()
+        .chain(profiles)
+        .collect::<Vec<_>>();
+    let pinned = |name: &str| {
+        deps.iter()
+            .filter(|(n, _)| *n == name)
+            .any(|(_, dep)| dep.file().is_some() || dep.version().is_some())
+    };
+
+    let mut outdated = vec![];
+    for locked in locked {
+        let Some(pos) = found.iter().position(|m| Some(m.id) == locked.id) else {
+            eprintln!("warning: mod `{}` no longer exists", locked.name);
+            continue;
+        };
+        let Some(latest) = found.swap_remove(pos).modfile else {
+            eprintln!("warning: mod `{}` has no primary file", locked.name);
+            continue;
+        };
+        if Some(latest.id) != locked.file {
+            outdated.push(Outdated {
+                name: locked.name.clone(),
+                current: locked.version.clone(),
+                latest,
+                pinned: pinned(&locked.name),
+            });
+        }
+    }
+    Ok(outdated)
+}
+
+/// Compare the newest modfiles of the subscribed mods in the download cache with the primary
+/// files. Mods without a downloaded modfile are skipped.
+async fn outdated_subscriptions(
+    config: &Config,
+    modio: &Modio,
+    filter: Filter,
+) -> Result<Vec<Outdated>> {
+    let retry = config.retry();
+    let cache = config.cache();
+    let subs = retry.run(|| subscriptions(modio, filter.clone())).await?;
+
+    let mods = subs
+        .into_iter()
+        .flat_map(|(_, mods)| mods)
+        .filter_map(|m: Mod| {
+            let file = m.modfile.as_ref()?;
+            let cached = cache.contains(file.id, &file.filehash.md5, &file.filename);
+            (!cached).then_some(m)
+        });
+    let tasks = mods.map(|mut m| {
+        let files = modio.mod_(m.game_id, m.id).files();
+        async move {
+            let all = retry
+                .run(|| files.search(Filter::default()).collect())
+                .await;
+            let latest = m.modfile.take().expect("filtered");
+            (m.name_id, latest, all)
+        }
+    });
+    let mut st = stream::iter(tasks).buffer_unordered(config.jobs());
+
+    let mut outdated = vec![];
+    while let Some((name, latest, files)) = st.next().await {
+        let current = files?
+            .into_iter()
+            .filter(|f| cache.contains(f.id, &f.filehash.md5, &f.filename))
+            .max_by_key(|f| f.date_added.as_secs());
+        if let Some(current) = current {
+            outdated.push(Outdated {
+                name,
+                current: current.version,
+                latest,
+                pinned: false,
+            });
+        }
+    }
+    outdated.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(outdated)
+}
+
+fn print(outdated: &[Outdated]) {
+    let mut output = Table::new();
+    output.set_format(*format::consts::FORMAT_CLEAN);
+    output.set_titles(row![b -> "Mod", b -> "Current", b -> "Latest", b -> "Updated"]);
+    for o in outdated {
+        let mut current = o.current.clone().unwrap_or_else(|| "-".to_owned());
+        if o.pinned {
+            current.push_str(" (pinned)");
+        }
+        output.add_row(row![
+            o.name,
+            current,
+            o.latest.version.as_deref().unwrap_or("-"),
+            format_date(o.latest.date_added.as_secs()),
+        ]);
+    }
+    output.printstd();
+}
@@ -0,0 +1,31 @@
+use modiom::edit::ManifestEditor;
+
+use crate::command_prelude::*;
+
+pub fn cli() -> Command {
+    Command::new("remove")
+        .about("Remove a mod from Modio.toml")
+        .arg(
+            Arg::new("name")
+                .help("Key of the mod in the manifest.")
+                .value_name("NAME")
+                .required(true),
+        )
+        .arg_manifest_path()
+}
+
+pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
+    let path = args.root_manifest(config)?;
+    let name = args.get_string("name").expect("required arg");
+
+    let mut editor = ManifestEditor::open(&path)?;
+    let Some(refs) = editor.remove_mod(name) else {
+        return Err(Error::not_found(format!("mod `{name}` is not in the manifest")).into());
+    };
+    editor.write()?;
+    println!("Removing: {name}");
+    for r in refs {
+        println!("Removing: {name} from `{r}`");
+    }
+    Ok(())
+}
@@ -1,9 +1,10 @@
 use prettytable::{format, Table};
 use tokio::runtime::Runtime;
 
-use modio::filter::custom_filter;
 use modio::filter::prelude::*;
+use modio::filter::{custom_filter, Operator};
 use modio::types::id::GameId;
+use modiom::policy::describe;
 
 use crate::command_prelude::*;
 use crate::commands::expr;
@@ -34,6 +35,13 @@ pub fn cli() -> Command {
                 .value_name("EXPR")
                 .action(ArgAction::Append),
         )
+        .arg(
+            opt(
+                "ignore-policy",
+                "Include mods that exceed the content policy of the config.",
+            )
+            .action(ArgAction::SetTrue),
+        )
 }
 
 pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
@@ -63,6 +71,7 @@ pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
 
     let rt = Runtime::new()?;
     let m = client(config)?;
+    let retry = config.retry();
 
     if let Some(game_id) = game_id {
         let mut f = Filter::default();
@@ -74,28 +83,35 @@ pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
             filter.add_row(row![format!("fulltext = {ft:?}")]);
             f = f.and(Fulltext::eq(ft));
         }
+        let policy = config.policy();
+        if let (Some(allowed), Some(values)) = (policy.maturity, policy.allowed_values()) {
+            if !args.get_flag("ignore-policy") {
+                filter.add_row(row![format!("maturity <= [{}]", describe(allowed))]);
+                f = f.and(custom_filter("maturity_option", Operator::In, values));
+            }
+        }
         if !filter.is_empty() {
             filter.printstd();
             println!();
         }
 
-        let list = rt.block_on(m.game(game_id).mods().search(f).first_page());
-        if let Ok(list) = list {
-            let mut output = Table::new();
-            output.set_format(*format::consts::FORMAT_CLEAN);
-            output.add_row(row!(
-                b -> "Id",
-                b -> "Name-Id",
-                b -> "Name",
-            ));
-            for m in list {
-                output.add_row(row![m.id, m.name_id, m.name]);
-            }
-            if output.is_empty() {
-                output.add_row(row![H3 -> "No results"]);
-            }
-            output.printstd();
+        let mods = m.game(game_id).mods();
+        let list = rt.block_on(retry.run(|| mods.search(f.clone()).first_page()));
+        let list = list?;
+        let mut output = Table::new();
+        output.set_format(*format::consts::FORMAT_CLEAN);
+        output.add_row(row!(
+            b -> "Id",
+            b -> "Name-Id",
+            b -> "Name",
+        ));
+        for m in list {
+            output.add_row(row![m.id, m.name_id, m.name]);
+        }
+        if output.is_empty() {
+            output.add_row(row![H3 -> "No results"]);
         }
+        output.printstd();
     } else {
         let mut f = Filter::default();
         for e in exprs {
@@ -111,23 +127,22 @@ pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
             println!();
         }
 
-        let list = rt.block_on(m.games().search(f).first_page());
-        if let Ok(list) = list {
-            let mut output = Table::new();
-            output.set_format(*format::consts::FORMAT_CLEAN);
-            output.set_titles(row![
-                b -> "Id",
-                b -> "Name-Id",
-                b -> "Name",
-            ]);
-            for g in list {
-                output.add_row(row![g.id, g.name_id, g.name]);
-            }
-            if output.is_empty() {
-                output.add_row(row![H3 -> "No results"]);
-            }
-            output.printstd();
+        let list = rt.block_on(retry.run(|| m.games().search(f.clone()).first_page()));
+        let list = list?;
+        let mut output = Table::new();
+        output.set_format(*format::consts::FORMAT_CLEAN);
+        output.set_titles(row![
+            b -> "Id",
+            b -> "Name-Id",
+            b -> "Name",
+        ]);
+        for g in list {
+            output.add_row(row![g.id, g.name_id, g.name]);
+        }
+        if output.is_empty() {
+            output.add_row(row![H3 -> "No results"]);
         }
+        output.printstd();
     }
     Ok(())
 }
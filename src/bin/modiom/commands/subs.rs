@@ -5,9 +5,11 @@ use prettytable::{format, Table};
 use tokio::runtime::Runtime;
 
 use modio::filter::prelude::*;
+use modio::types::games::Game;
 use modio::types::id;
 use modio::types::mods::Mod;
 use modio::user::filters::subscriptions::GameId;
+use modio::Modio;
 
 use crate::command_prelude::*;
 
@@ -80,26 +82,7 @@ fn list_subs(config: &Config, args: &ArgMatches) -> CliResult {
         Filter::default()
     };
 
-    let task = async {
-        let st = m.user().subscriptions(filter).iter().await?;
-        let mut subs = st
-            .try_fold(Subs::new(), |mut subs, m| async {
-                subs.entry(m.game_id).or_default().push(m);
-                Ok(subs)
-            })
-            .await?;
-        let filter = Id::_in(subs.keys().collect::<Vec<_>>());
-        m.games()
-            .search(filter)
-            .iter()
-            .await?
-            .map_ok(move |g| subs.remove(&g.id).map(|mods| (g, mods)))
-            .try_filter_map(|s| async { Ok(s) })
-            .try_collect::<Vec<_>>()
-            .await
-    };
-
-    let subs = rt.block_on(task)?;
+    let subs = rt.block_on(config.retry().run(|| subscriptions(&m, filter.clone())))?;
     for (game, mods) in subs {
         let mut output = Table::new();
         output.set_format(*format::consts::FORMAT_CLEAN);
@@ -117,14 +100,38 @@ fn list_subs(config: &Config, args: &ArgMatches) -> CliResult {
     Ok(())
 }
 
+/// Returns the subscribed mods matching the filter grouped by their game.
+pub async fn subscriptions(modio: &Modio, filter: Filter) -> modio::Result<Vec<(Game, Vec<Mod>)>> {
+    let st = modio.user().subscriptions(filter).iter().await?;
+    let mut subs = st
+        .try_fold(Subs::new(), |mut subs, m| async {
+            subs.entry(m.game_id).or_default().push(m);
+            Ok(subs)
+        })
+        .await?;
+    let filter = Id::_in(subs.keys().collect::<Vec<_>>());
+    modio
+        .games()
+        .search(filter)
+        .iter()
+        .await?
+        .map_ok(move |g| subs.remove(&g.id).map(|mods| (g, mods)))
+        .try_filter_map(|s| async { Ok(s) })
+        .try_collect::<Vec<_>>()
+        .await
+}
+
 fn subscribe(config: &Config, args: &ArgMatches) -> CliResult {
     let game_id = *args.get_one("game").expect("required arg");
     let mod_id = *args.get_one("mod").expect("required arg");
 
     let rt = Runtime::new()?;
     let m = client(config)?;
+    let modref = m.mod_(game_id, mod_id);
 
-    rt.block_on(m.mod_(game_id, mod_id).subscribe())?;
+    let mod_ = rt.block_on(config.retry().run(|| modref.clone().get()))?;
+    config.policy().check(&mod_)?;
+    rt.block_on(modref.subscribe())?;
     Ok(())
 }
 
@@ -0,0 +1,322 @@
+use std::collections::{HashMap, HashSet};
+
+use futures::{stream, StreamExt};
+use modio::filter::prelude::*;
+use modio::types::id::{GameId, ModId};
+use modiom::manifest::{self, Features, Identifier, ModDependency};
+use tokio::runtime::Runtime;
+
+use crate::command_prelude::*;
+use crate::resolve::Resolver;
+
+pub fn cli() -> Command {
+    Command::new("tree")
+        .about("Show the dependency tree of a mod or the mods of Modio.toml")
+        .arg(
+            Arg::new("mod")
+                .help("Name of a mod in Modio.toml or name-id or unique id of a mod with --game.")
+                .value_name("MOD"),
+        )
+        .arg(
+            opt(
+                "game",
+                "Name-id or unique id of the game to look up MOD instead of using Modio.toml.",
+            )
+            .value_name("GAME")
+            .requires("mod"),
+        )
+        .arg(
+            opt(
+                "invert",
+                "Show the mods of Modio.toml that depend on MOD and pull it in.",
+            )
+            .short('i')
+            .action(ArgAction::SetTrue)
+            .requires("mod")
+            .conflicts_with("game"),
+        )
+        .arg_manifest_path()
+}
+
+/// A mod of the dependency graph.
+struct Node {
+    /// Key of the mod in the manifest or the name-id.
+    name: String,
+    name_id: String,
+    version: Option<String>,
+    dependencies: Vec<ModId>,
+}
+
+impl Node {
+    fn label(&self) -> String {
+        let mut label = self.name.clone();
+        if self.name != self.name_id {
+            label.push_str(&format!(" ({})", self.name_id));
+        }
+        if let Some(version) = &self.version {
+            label.push(' ');
+            label.push_str(version);
+        }
+        label
+    }
+}
+
+type Graph = HashMap<ModId, Node>;
+
+pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
+    let rt = Runtime::new()?;
+    let modio = client(config)?;
+    let resolver = Resolver {
+        config,
+        modio: &modio,
+        jobs: config.jobs(),
+        keep_going: false,
+        policy: None,
+        dependency_graph: true,
+    };
+    let name = args.get_string("mod");
+
+    if let Some(game) = args.get_string("game") {
+        let name = name.expect("required by --game");
+        let dep = ModDependency::Simple(Identifier::from(name.as_str()));
+        let (graph, root) = rt.block_on(async {
+            let game_id = resolver.game_id(&Identifier::from(game.as_str())).await?;
+            let (mod_, _) = resolver.resolve_mod(game_id, &dep).await?;
+            let mut graph = Graph::new();
+            crawl(&resolver, game_id, &mut graph, vec![mod_.id]).await?;
+            Ok::<_, Box<dyn std::error::Error>>((graph, mod_.id))
+        })?;
+        print_tree(&graph, root, &dependencies(&graph));
+        return Ok(());
+    }
+
+    let path = args.root_manifest(config)?;
+    let manifest = manifest::read(&path)?;
+    // Mods with a local source have no dependencies on mod.io.
+    let mods = manifest::remote_mods(&manifest.select_mods(None, Features::All)?);
+    let game = manifest.game;
+
+    let (graph, mut roots) = rt.block_on(async {
+        let game_id = resolver.game_id(&game.id).await?;
+        let resolution = resolver.resolve(game_id, &game, &mods).await?;
+
+        let mut graph = Graph::new();
+        let mut roots = vec![];
+        for r in resolution.mods {
+            if r.dependency_of.is_none() {
+                roots.push(r.mod_.id);
+            }
+            let node = Node {
+                name: r.name,
+                name_id: r.mod_.name_id,
+                version: r.file.version,
+                dependencies: r.dependencies,
+            };
+            graph.insert(r.mod_.id, node);
+        }
+        let mut unknown = vec![];
+        for node in graph.values() {
+            for id in &node.dependencies {
+                if !graph.contains_key(id) && !unknown.contains(id) {
+                    unknown.push(*id);
+                }
+            }
+        }
+        crawl(&resolver, game_id, &mut graph, unknown).await?;
+        Ok::<_, Box<dyn std::error::Error>>((graph, roots))
+    })?;
+    roots.sort_by(|a, b| graph[a].name.cmp(&graph[b].name));
+
+    let find = |name: &str| {
+        let id = roots
+            .iter()
+            .copied()
+            .find(|id| graph[id].name == name)
+            .or_else(|| {
+                graph
+                    .iter()
+                    .find(|(id, n)| n.name_id == name || id.to_string() == name)
+                    .map(|(id, _)| *id)
+            });
+        id.ok_or_else(|| {
+            Error::not_found(format!(
+                "mod `{name}` is neither part of the manifest nor a dependency"
+            ))
+        })
+    };
+
+    if args.get_flag("invert") {
+        let root = find(name.expect("required by --invert"))?;
+        let mut dependents = HashMap::<ModId, Vec<ModId>>::new();
+        for (id, node) in &graph {
+            for dep in &node.dependencies {
+                dependents.entry(*dep).or_default().push(*id);
+            }
+        }
+        let children = |id: ModId| {
+            let ids = dependents.get(&id).cloned().unwrap_or_default();
+            sorted(&graph, ids)
+        };
+        print_tree(&graph, root, &children);
+        return Ok(());
+    }
+
+    if let Some(name) = name {
+        roots = vec![find(name)?];
+    }
+    for (i, root) in roots.into_iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        print_tree(&graph, root, &dependencies(&graph));
+    }
+    Ok(())
+}
+
+/// Look up the mods and their transitive dependencies level by level.
+async fn crawl(
+    resolver: &Resolver<'_>,
+    game_id: GameId,
+    graph: &mut Graph,
+    mut ids: Vec<ModId>,
+) -> Result<()> {
+    let retry = resolver.config.retry();
+    while !ids.is_empty() {
+        let mods = resolver.modio.game(game_id).mods();
+        let found = retry
+            .run(|| mods.search(Id::_in(ids.clone())).collect())
+            .await?;
+
+        let mut lookup = vec![];
+        for m in found {
+            if m.dependencies {
+                lookup.push(m.id);
+            }
+            let node = Node {
+                name: m.name_id.clone(),
+                name_id: m.name_id,
+                version: m.modfile.and_then(|f| f.version),
+                dependencies: vec![],
+            };
+            graph.insert(m.id, node);
+        }
+        for id in &ids {
+            graph.entry(*id).or_insert_with(|| Node {
+                name: id.to_string(),
+                name_id: "not found".to_owned(),
+                version: None,
+                dependencies: vec![],
+            });
+        }
+
+        let tasks = lookup.iter().map(|id| {
+            let deps = resolver.modio.mod_(game_id, *id).dependencies();
+            async move { (*id, retry.run(|| deps.clone().list()).await) }
+        });
+        let mut st = stream::iter(tasks).buffer_unordered(resolver.jobs);
+        ids = vec![];
+        while let Some((id, res)) = st.next().await {
+            let deps = res?.into_iter().map(|d| d.mod_id).collect::<Vec<_>>();
+            for dep in &deps {
+                if !graph.contains_key(dep) && !ids.contains(dep) {
+                    ids.push(*dep);
+                }
+            }
+            if let Some(node) = graph.get_mut(&id) {
+                node.dependencies = deps;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn sorted(graph: &Graph, mut ids: Vec<ModId>) -> Vec<ModId> {
+    ids.sort_by(|a, b| graph[a].name.cmp(&graph[b].name));
+    ids.dedup();
+    ids
+}
+
+fn dependencies(graph: &Graph) -> impl Fn(ModId) -> Vec<ModId> + '_ {
+    move |id| sorted(graph, graph[&id].dependencies.clone())
+}
+
+/// Print the tree below `root`. Mods that are shown more than once are marked with `(*)` and
+/// not expanded again.
+fn print_tree(graph: &Graph, root: ModId, children: &dyn Fn(ModId) -> Vec<ModId>) {
+    print!("{}", render_tree(graph, root, children));
+}
+
+fn render_tree(graph: &Graph, root: ModId, children: &dyn Fn(ModId) -> Vec<ModId>) -> String {
+    let mut out = format!("{}\n", graph[&root].label());
+    let mut seen = HashSet::from([root]);
+    render_children(
+        graph,
+        root,
+        children,
+        &mut String::new(),
+        &mut seen,
+        &mut out,
+    );
+    out
+}
+
+fn render_children(
+    graph: &Graph,
+    id: ModId,
+    children: &dyn Fn(ModId) -> Vec<ModId>,
+    prefix: &mut String,
+    seen: &mut HashSet<ModId>,
+    out: &mut String,
+) {
+    let ids = children(id);
+    for (i, child) in ids.iter().enumerate() {
+        let last = i + 1 == ids.len();
+        let branch = if last { "└── " } else { "├── " };
+        let first = seen.insert(*child);
+        let marker = if first { "" } else { " (*)" };
+        out.push_str(&format!(
+            "{prefix}{branch}{}{marker}\n",
+            graph[child].label()
+        ));
+        if first {
+            let len = prefix.len();
+            prefix.push_str(if last { "    " } else { "│   " });
+            render_children(graph, *child, children, prefix, seen, out);
+            prefix.truncate(len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, version: Option<&str>, dependencies: &[u64]) -> Node {
+        Node {
+            name: name.to_owned(),
+            name_id: name.to_owned(),
+            version: version.map(str::to_owned),
+            dependencies: dependencies.iter().map(|id| ModId::new(*id)).collect(),
+        }
+    }
+
+    #[test]
+    fn render() {
+        let mut graph = Graph::new();
+        graph.insert(ModId::new(1), node("maps", Some("1.0"), &[3, 2]));
+        graph.insert(ModId::new(2), node("core", Some("2.1"), &[4]));
+        graph.insert(ModId::new(3), node("assets", None, &[4]));
+        graph.insert(ModId::new(4), node("zlib", Some("1.3"), &[]));
+        graph.get_mut(&ModId::new(1)).unwrap().name = "texpack".to_owned();
+
+        let expected = "\
+texpack (maps) 1.0
+├── assets
+│   └── zlib 1.3
+└── core 2.1
+    └── zlib 1.3 (*)
+";
+        let tree = render_tree(&graph, ModId::new(1), &dependencies(&graph));
+        assert_eq!(tree, expected);
+    }
+}
@@ -1,15 +1,13 @@
 use std::borrow::Cow;
 
-use futures::{future::try_join3, StreamExt};
+use futures::future::try_join3;
 use prettytable::format;
 use tokio::fs::{self, File};
-use tokio::io::BufReader;
 use tokio::runtime::Runtime;
-use tokio_util::io::ReaderStream;
 
 use modio::files::AddFileOptions;
 use modio::types::id::{GameId, ModId};
-use modiom::md5::Md5;
+use modiom::md5;
 
 use crate::command_prelude::*;
 
@@ -79,19 +77,13 @@ pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
         src.file_name()
             .and_then(|n| n.to_str())
             .map(ToString::to_string)
-            .ok_or("Failed to get the filename")?
+            .ok_or_else(|| Error::usage("Failed to get the filename"))?
             .into()
     };
 
     let checksum = async {
         if args.get_flag("checksum") {
-            let r = File::open(&src).await?;
-            let r = BufReader::with_capacity(512 * 512, r);
-            let st = ReaderStream::new(r);
-            let mut md5 = Md5::default();
-            st.forward(&mut md5).await?;
-
-            Ok(Some(md5.into_lower_hex()))
+            md5::file_hash(src).await.map(Some)
         } else {
             Ok(None)
         }
@@ -124,21 +116,17 @@ pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
         Ok::<_, Box<dyn std::error::Error>>(file)
     };
 
-    match rt.block_on(upload) {
-        Ok(file) => {
-            let mut ft = table!(
-                [bH2 -> "Uploaded File"],
-                [b -> "Id", file.id],
-                [b -> "Filename", file.filename],
-                [b -> "Version", file.version.unwrap_or_default()],
-                [b -> "Download", file.download.binary_url],
-                [b -> "Size", file.filesize],
-                [b -> "MD5", file.filehash.md5]
-            );
-            ft.set_format(*format::consts::FORMAT_CLEAN);
-            ft.printstd();
-        }
-        Err(e) => println!("{e}"),
-    }
+    let file = rt.block_on(upload)?;
+    let mut ft = table!(
+        [bH2 -> "Uploaded File"],
+        [b -> "Id", file.id],
+        [b -> "Filename", file.filename],
+        [b -> "Version", file.version.unwrap_or_default()],
+        [b -> "Download", file.download.binary_url],
+        [b -> "Size", file.filesize],
+        [b -> "MD5", file.filehash.md5]
+    );
+    ft.set_format(*format::consts::FORMAT_CLEAN);
+    ft.printstd();
     Ok(())
 }
@@ -0,0 +1,215 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use modio::types::id::GameId;
+use modiom::extract::{self, ExtractOptions};
+use modiom::local;
+use modiom::lockfile::{self, LockedMod};
+use modiom::manifest;
+use modiom::md5;
+use modiom::state::{self, InstalledMod};
+use modiom::verify::{self, Report};
+use tokio::runtime::Runtime;
+
+use crate::command_prelude::*;
+
+pub fn cli() -> Command {
+    Command::new("verify")
+        .about("Check the installed files of Modio.toml against the recorded checksums")
+        .arg(
+            opt(
+                "repair",
+                "Reinstall the mods with modified or missing files.",
+            )
+            .action(ArgAction::SetTrue),
+        )
+        .arg_allow_unscanned()
+        .arg_manifest_path()
+}
+
+pub fn exec(config: &Config, args: &ArgMatches) -> CliResult {
+    let path = args.root_manifest(config)?;
+    let manifest = manifest::read(&path)?;
+    let root = path.parent().unwrap_or_else(|| Path::new("."));
+    let lock_path = lockfile::path_for(&path);
+    let lock = lockfile::read(&lock_path)?.ok_or_else(|| {
+        Error::not_found(format!(
+            "`{}` not found, run `modiom install` first",
+            lock_path.display()
+        ))
+    })?;
+    let state_path = state::path_for(root);
+    let mut state = state::read(&state_path)?;
+
+    let installed = lock
+        .mods
+        .iter()
+        .map(|l| match state.get(&l.name) {
+            Some(installed) => installed.clone(),
+            None => InstalledMod::from_locked(l),
+        })
+        .collect::<Vec<_>>();
+
+    // Files of other mods and the load order file aren't extra files.
+    let mut known = installed
+        .iter()
+        .flat_map(InstalledMod::paths)
+        .collect::<HashSet<_>>();
+    if let Some(load_order) = &manifest.load_order {
+        known.insert(&load_order.file);
+    }
+
+    let rt = Runtime::new()?;
+    let mut reported = HashSet::new();
+    let mut broken = vec![];
+    for (locked, installed) in lock.mods.iter().zip(&installed) {
+        let mut report = rt.block_on(verify::verify(root, installed))?;
+        if let Some(dir) = extract_dir(locked, installed)? {
+            report.extra = verify::extra_files(root, &dir, &known)?;
+            report.extra.retain(|p| reported.insert(p.clone()));
+        }
+        print_report(&installed.name, installed.files.len(), &report);
+        if report.is_broken() {
+            broken.push((locked, installed));
+        }
+    }
+
+    if broken.is_empty() {
+        return Ok(());
+    }
+    if !args.get_flag("repair") {
+        return Err(Error::integrity(format!(
+            "{} mod(s) with modified or missing files, run `modiom verify --repair` to reinstall them",
+            broken.len()
+        ))
+        .into());
+    }
+
+    for (locked, installed) in broken {
+        println!("Repairing: {}", installed.name);
+        let archive = rt.block_on(archive(config, args, root, lock.game, locked))?;
+        let repaired = reinstall(root, &archive, locked, installed)?;
+        match state.mods.iter_mut().find(|m| m.name == repaired.name) {
+            Some(m) => *m = repaired,
+            None => state.mods.push(repaired),
+        }
+    }
+    state.mods.sort_by(|a, b| a.name.cmp(&b.name));
+    state::write(&state_path, &state)?;
+    Ok(())
+}
+
+/// Returns the extract directory of the mod. Extra files aren't detected for mods extracted
+/// into the directory of the manifest.
+fn extract_dir(locked: &LockedMod, installed: &InstalledMod) -> Result<Option<PathBuf>> {
+    let Some(extract) = &installed.extract else {
+        return Ok(None);
+    };
+    let opts = ExtractOptions::new(extract, &installed.name, &locked.name_id, installed.id)?;
+    if extract::to_slash(&opts.dir).is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(opts.dir))
+}
+
+fn print_report(name: &str, files: usize, report: &Report) {
+    if report.is_ok() {
+        println!("Ok: {name} ({files} files)");
+        return;
+    }
+    let level = if report.is_broken() {
+        "error"
+    } else {
+        "warning"
+    };
+    eprintln!(
+        "{level}: {name}: {} modified, {} missing, {} extra file(s)",
+        report.modified.len(),
+        report.missing.len(),
+        report.extra.len()
+    );
+    for path in &report.modified {
+        eprintln!("  modified: {path}");
+    }
+    for path in &report.missing {
+        eprintln!("  missing:  {path}");
+    }
+    for path in &report.extra {
+        eprintln!("  extra:    {path}");
+    }
+}
+
+/// Returns the archive of the local source or the cached archive of the locked modfile and
+/// downloads it if necessary. Local sources that changed since the install are refused.
+async fn archive(
+    config: &Config,
+    args: &ArgMatches,
+    root: &Path,
+    game_id: Option<GameId>,
+    locked: &LockedMod,
+) -> Result<PathBuf> {
+    if let Some(source) = &locked.local {
+        let archive = local::archive(root, &locked.name, source, &local::pack_dir(root))?;
+        if !md5::file_hash(&archive)
+            .await?
+            .eq_ignore_ascii_case(&locked.md5)
+        {
+            return Err(Error::integrity(format!(
+                "the local source `{source}` of mod `{}` changed since the install, run `modiom install` instead",
+                locked.name
+            ))
+            .into());
+        }
+        return Ok(archive);
+    }
+    if let Some(archive) = cached_archive(config, locked).await? {
+        return Ok(archive);
+    }
+    let file_id = locked.file.ok_or_else(|| {
+        Error::not_found(format!(
+            "mod `{}` has no modfile in Modio.lock",
+            locked.name
+        ))
+    })?;
+    let game_id = game_id.ok_or_else(|| {
+        Error::not_found(format!(
+            "`{}` is not in the download cache and Modio.lock has no game id",
+            locked.filename
+        ))
+    })?;
+    let mod_id = locked.id.ok_or_else(|| {
+        Error::not_found(format!("mod `{}` has no mod id in Modio.lock", locked.name))
+    })?;
+    let modio = client(config)?;
+    let files = modio.mod_(game_id, mod_id).files();
+    let file = config.retry().run(|| files.get(file_id).get()).await?;
+    let allow_unscanned = args.get_flag("allow-unscanned");
+    fetch_modfile(config, &http_client()?, &file, allow_unscanned).await
+}
+
+/// Restore the recorded files of the mod from the archive.
+fn reinstall(
+    root: &Path,
+    archive: &Path,
+    locked: &LockedMod,
+    installed: &InstalledMod,
+) -> Result<InstalledMod> {
+    let mut repaired = installed.clone();
+    match &installed.extract {
+        Some(extract) => {
+            let opts =
+                ExtractOptions::new(extract, &installed.name, &locked.name_id, installed.id)?;
+            let recorded = installed
+                .files
+                .iter()
+                .map(|f| f.path.as_str())
+                .collect::<HashSet<_>>();
+            repaired.files = extract::extract(archive, root, &opts, |p| recorded.contains(p))?;
+        }
+        None => {
+            fs::copy(archive, root.join(&locked.filename))?;
+        }
+    }
+    Ok(repaired)
+}
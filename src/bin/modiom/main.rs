@@ -1,9 +1,12 @@
 mod command_prelude;
 mod commands;
+mod plan;
+mod resolve;
+mod summary;
 
 use crate::command_prelude::*;
 
-fn main() -> CliResult {
+fn main() {
     let args = Command::new("modiom")
         .subcommand_required(true)
         .arg_required_else_help(true)
@@ -13,17 +16,27 @@ fn main() -> CliResult {
                 .global(true)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            opt(
+                "retry",
+                "Number of retries for failed requests and downloads",
+            )
+            .value_name("N")
+            .global(true)
+            .value_parser(value_parser!(u32)),
+        )
         .try_get_matches()
         .unwrap_or_else(|e| e.exit());
 
+    if let Err(e) = run(&args) {
+        eprintln!("error: {e}");
+        std::process::exit(e.exit_code());
+    }
+}
+
+fn run(args: &ArgMatches) -> CliResult {
     let mut config = Config::default()?;
-    config.configure(args.is_test_env())?;
+    config.configure(args.is_test_env(), args.get_one("retry").copied())?;
 
-    match commands::exec(&config, &args) {
-        Err(e) => {
-            eprintln!("{e}");
-            std::process::exit(1);
-        }
-        Ok(()) => Ok(()),
-    }
+    commands::exec(&config, args)
 }
@@ -0,0 +1,100 @@
+use prettytable::{format, Table};
+
+use modiom::utils::format_size;
+
+use crate::command_prelude::*;
+
+pub enum Action {
+    /// The archive is downloaded.
+    Download,
+    /// The archive is installed from the download cache.
+    Cached,
+    /// The archive is packed or copied from a local source.
+    Local,
+    /// The installed modfile is already the selected one.
+    UpToDate,
+    /// The installed modfile is no longer part of the manifest.
+    Remove,
+}
+
+struct Step {
+    name: String,
+    version: Option<String>,
+    filename: String,
+    size: u64,
+    action: Action,
+}
+
+/// The steps of `install` and `download` printed with `--dry-run`.
+#[derive(Default)]
+pub struct Plan {
+    steps: Vec<Step>,
+}
+
+impl Plan {
+    pub fn add(
+        &mut self,
+        name: impl Into<String>,
+        version: Option<String>,
+        filename: impl Into<String>,
+        size: u64,
+        action: Action,
+    ) {
+        self.steps.push(Step {
+            name: name.into(),
+            version,
+            filename: filename.into(),
+            size,
+            action,
+        });
+    }
+
+    pub fn print(&mut self) {
+        self.steps.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let (mut fetch, mut fetch_size) = (0, 0);
+        let (mut cached, mut local, mut up_to_date, mut remove) = (0, 0, 0, 0);
+        let mut output = Table::new();
+        output.set_format(*format::consts::FORMAT_CLEAN);
+        output
+            .set_titles(row![b -> "Mod", b -> "Version", b -> "File", b -> "Size", b -> "Action"]);
+        for step in &self.steps {
+            let version = step.version.as_deref().unwrap_or("-");
+            let size = format_size(step.size);
+            let (name, filename) = (&step.name, &step.filename);
+            match step.action {
+                Action::Download => {
+                    fetch += 1;
+                    fetch_size += step.size;
+                    output.add_row(row![name, version, filename, r -> size, Fg -> "download"]);
+                }
+                Action::Cached => {
+                    cached += 1;
+                    output.add_row(row![name, version, filename, r -> size, Fg -> "from cache"]);
+                }
+                Action::Local => {
+                    local += 1;
+                    output.add_row(row![name, version, filename, r -> size, Fg -> "local"]);
+                }
+                Action::UpToDate => {
+                    up_to_date += 1;
+                    output.add_row(row![name, version, filename, r -> size, "up to date"]);
+                }
+                Action::Remove => {
+                    remove += 1;
+                    output.add_row(row![name, version, filename, r -> size, Fr -> "remove"]);
+                }
+            }
+        }
+        output.printstd();
+        println!();
+        let local = match local {
+            0 => String::new(),
+            n => format!(", {n} from local sources"),
+        };
+        println!(
+            "{fetch} to download ({}), {cached} from cache{local}, {up_to_date} up to date, {remove} to remove",
+            format_size(fetch_size)
+        );
+    }
+}
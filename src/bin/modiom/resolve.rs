@@ -0,0 +1,308 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::error::Error as StdError;
+
+use futures::{stream, StreamExt};
+use modio::files::filters::Version;
+use modio::filter::prelude::*;
+use modio::types::files::File;
+use modio::types::id::{FileId, GameId, ModId};
+use modio::types::mods::Mod;
+use modio::Modio;
+use modiom::manifest::{Game, Identifier, ModDependencies, ModDependency};
+use modiom::policy::Policy;
+use modiom::version::{parse_version, VersionSpec};
+
+use crate::command_prelude::*;
+
+/// A mod of the manifest or one of their dependencies with the selected modfile.
+pub struct ResolvedMod {
+    /// Key of the mod in the manifest or the name-id of a dependency.
+    pub name: String,
+    pub mod_: Mod,
+    pub file: File,
+    /// Name of the mod that pulled in the dependency.
+    pub dependency_of: Option<String>,
+    /// The mods the mod depends on.
+    pub dependencies: Vec<ModId>,
+}
+
+#[derive(Default)]
+pub struct Resolution {
+    pub mods: Vec<ResolvedMod>,
+    /// Mods that could not be resolved with `keep_going` enabled.
+    pub failed: Vec<(String, Box<dyn StdError>)>,
+}
+
+/// Looks up the mods of a manifest and selects their modfiles.
+pub struct Resolver<'a> {
+    pub config: &'a Config,
+    pub modio: &'a Modio,
+    pub jobs: usize,
+    /// Record failed mods in the resolution instead of returning the first error.
+    pub keep_going: bool,
+    /// Refuse mods and dependencies that the content policy doesn't allow.
+    pub policy: Option<&'a Policy>,
+    /// Look up the dependencies of all mods to record the dependency graph, e.g. for the load
+    /// order. Otherwise only the mods that pull in their dependencies are looked up.
+    pub dependency_graph: bool,
+}
+
+impl Resolver<'_> {
+    pub async fn game_id(&self, id: &Identifier) -> Result<GameId> {
+        match id {
+            Identifier::Id(id) => Ok(GameId::new(*id)),
+            Identifier::NameId(id) => {
+                let filter = NameId::eq(id);
+                let games = self.modio.games();
+                let first = self
+                    .config
+                    .retry()
+                    .run(|| games.search(filter.clone()).first())
+                    .await?;
+                match first {
+                    Some(game) => Ok(game.id),
+                    None => {
+                        Err(Error::not_found(format!("no matching game named `{id}` found")).into())
+                    }
+                }
+            }
+        }
+    }
+
+    /// Resolve the mods and, if enabled for the mod or the game, their dependencies.
+    pub async fn resolve(
+        &self,
+        game_id: GameId,
+        game: &Game,
+        mods: &ModDependencies,
+    ) -> Result<Resolution> {
+        let tasks = mods
+            .iter()
+            .map(|(name, dep)| async move { (name, dep, self.resolve_mod(game_id, dep).await) });
+        let mut st = stream::iter(tasks).buffer_unordered(self.jobs);
+
+        let mut resolution = Resolution::default();
+        let mut parents = vec![];
+        while let Some((name, dep, res)) = st.next().await {
+            let res = res.and_then(|(mod_, file)| {
+                self.check_policy(&mod_)?;
+                Ok((mod_, file))
+            });
+            match res {
+                Ok((mod_, file)) => {
+                    let with_deps = dep
+                        .with_dependencies()
+                        .or(game.with_dependencies)
+                        .unwrap_or(false);
+                    if mod_.dependencies && (with_deps || self.dependency_graph) {
+                        parents.push((name.clone(), mod_.id, with_deps));
+                    }
+                    resolution.mods.push(ResolvedMod {
+                        name: name.clone(),
+                        mod_,
+                        file,
+                        dependency_of: None,
+                        dependencies: vec![],
+                    });
+                }
+                Err(e) if self.keep_going => resolution.failed.push((name.clone(), e)),
+                Err(e) => return Err(e),
+            }
+        }
+        drop(st);
+
+        self.resolve_dependencies(game_id, parents, &mut resolution)
+            .await?;
+
+        resolution.mods.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(resolution)
+    }
+
+    /// Look up the dependencies of `parents` level by level. The dependencies of parents with
+    /// the pull-in flag set are resolved as well.
+    async fn resolve_dependencies(
+        &self,
+        game_id: GameId,
+        mut parents: Vec<(String, ModId, bool)>,
+        resolution: &mut Resolution,
+    ) -> Result<()> {
+        let retry = self.config.retry();
+        let mut seen = resolution
+            .mods
+            .iter()
+            .map(|m| m.mod_.id)
+            .collect::<HashSet<_>>();
+        let mut names = resolution
+            .mods
+            .iter()
+            .map(|m| m.name.clone())
+            .collect::<BTreeSet<_>>();
+
+        while !parents.is_empty() {
+            let tasks = parents.iter().map(|(parent, mod_id, pull_in)| async move {
+                let deps = self.modio.mod_(game_id, *mod_id).dependencies();
+                let res = retry.run(|| deps.clone().list()).await;
+                (parent, *pull_in, res)
+            });
+            let mut st = stream::iter(tasks).buffer_unordered(self.jobs);
+
+            // The first parent that pulls in a dependency is recorded.
+            let mut pulled_in = vec![];
+            while let Some((parent, pull_in, res)) = st.next().await {
+                match res {
+                    Ok(deps) => {
+                        let ids = deps.iter().map(|dep| dep.mod_id).collect::<Vec<_>>();
+                        for id in ids.iter().filter(|_| pull_in) {
+                            if seen.insert(*id) {
+                                pulled_in.push((parent.clone(), *id));
+                            }
+                        }
+                        if let Some(m) = resolution.mods.iter_mut().find(|m| m.name == *parent) {
+                            m.dependencies = ids;
+                        }
+                    }
+                    Err(e) if self.keep_going => resolution.failed.push((parent.clone(), e.into())),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            drop(st);
+            if pulled_in.is_empty() {
+                break;
+            }
+
+            let ids = pulled_in.iter().map(|(_, id)| *id).collect::<Vec<_>>();
+            let mods = self.modio.game(game_id).mods();
+            let found = retry
+                .run(|| mods.search(Id::_in(ids.clone())).collect())
+                .await?;
+            let mut found = found
+                .into_iter()
+                .map(|m| (m.id, m))
+                .collect::<HashMap<_, _>>();
+
+            parents = vec![];
+            for (parent, mod_id) in pulled_in {
+                let Some(mut mod_) = found.remove(&mod_id) else {
+                    let e =
+                        Error::not_found(format!("dependency `{mod_id}` of `{parent}` not found"));
+                    if self.keep_going {
+                        resolution.failed.push((mod_id.to_string(), e.into()));
+                        continue;
+                    }
+                    return Err(e.into());
+                };
+                if let Err(e) = self.check_policy(&mod_) {
+                    let e = Error::new(e.kind(), format!("{e}, required by `{parent}`"));
+                    if self.keep_going {
+                        resolution.failed.push((mod_.name_id, e.into()));
+                        continue;
+                    }
+                    return Err(e.into());
+                }
+                let Some(file) = mod_.modfile.take() else {
+                    let e = Error::not_found(format!(
+                        "dependency `{}` of `{parent}` has no primary file",
+                        mod_.name_id
+                    ));
+                    if self.keep_going {
+                        resolution.failed.push((mod_.name_id, e.into()));
+                        continue;
+                    }
+                    return Err(e.into());
+                };
+                let mut name = mod_.name_id.clone();
+                if !names.insert(name.clone()) {
+                    name = format!("{}-{}", mod_.name_id, mod_.id);
+                    names.insert(name.clone());
+                }
+                if mod_.dependencies {
+                    parents.push((name.clone(), mod_.id, true));
+                }
+                resolution.mods.push(ResolvedMod {
+                    name,
+                    mod_,
+                    file,
+                    dependency_of: Some(parent),
+                    dependencies: vec![],
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_policy(&self, mod_: &Mod) -> std::result::Result<(), Error> {
+        match self.policy {
+            Some(policy) => policy.check(mod_),
+            None => Ok(()),
+        }
+    }
+
+    /// Look up the mod and select its modfile.
+    pub async fn resolve_mod(&self, game_id: GameId, dep: &ModDependency) -> Result<(Mod, File)> {
+        let retry = self.config.retry();
+        let Some(id) = dep.id() else {
+            let msg = "mods with a local source aren't available on mod.io";
+            return Err(Error::usage(msg).into());
+        };
+        let (filter, not_found) = match id {
+            Identifier::Id(id) => (Id::eq(id), format!("mod with id `{id}` not found")),
+            Identifier::NameId(id) => {
+                (NameId::eq(id), format!("mod with name-id `{id}` not found"))
+            }
+        };
+        let mods = self.modio.game(game_id).mods();
+        let mod_ = retry.run(|| mods.search(filter.clone()).first()).await?;
+        let Some(mut mod_) = mod_ else {
+            return Err(Error::not_found(not_found).into());
+        };
+        let file = match retry
+            .run(|| find_pinned_file(self.modio, &mod_, dep))
+            .await?
+        {
+            Some(file) => file,
+            None => mod_.modfile.take().ok_or_else(|| {
+                Error::not_found(format!("mod `{}` has no primary file", mod_.name_id))
+            })?,
+        };
+        Ok((mod_, file))
+    }
+}
+
+/// Returns the modfile selected by the `file` or `version` field of the dependency.
+async fn find_pinned_file(modio: &Modio, mod_: &Mod, dep: &ModDependency) -> Result<Option<File>> {
+    let files = modio.mod_(mod_.game_id, mod_.id).files();
+    if let Some(file_id) = dep.file() {
+        let file = files.get(FileId::new(u64::from(file_id))).get().await?;
+        return Ok(Some(file));
+    }
+    if let Some(version) = dep.version() {
+        let file = match VersionSpec::parse(version)? {
+            VersionSpec::Exact(version) => {
+                let filter = Version::eq(version).order_by(DateAdded::desc());
+                files.search(filter).first().await?
+            }
+            VersionSpec::Req(req) => {
+                let all = files.search(Filter::default()).collect().await?;
+                all.into_iter()
+                    .filter_map(|f| {
+                        let v = parse_version(f.version.as_deref()?)?;
+                        req.matches(&v).then_some((v, f))
+                    })
+                    .max_by(|(a, fa), (b, fb)| {
+                        a.cmp(b)
+                            .then(fa.date_added.as_secs().cmp(&fb.date_added.as_secs()))
+                    })
+                    .map(|(_, f)| f)
+            }
+        };
+        return match file {
+            Some(file) => Ok(Some(file)),
+            None => Err(Error::not_found(format!(
+                "mod `{}` has no file matching version `{version}`",
+                mod_.name_id
+            ))
+            .into()),
+        };
+    }
+    Ok(None)
+}
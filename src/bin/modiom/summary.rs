@@ -0,0 +1,78 @@
+use prettytable::{format, Table};
+
+use crate::command_prelude::*;
+
+pub enum Status {
+    Succeeded(String),
+    Skipped(String),
+    Failed(String),
+}
+
+/// Outcome of each mod of `install` and `download` with `--keep-going`.
+#[derive(Default)]
+pub struct Summary {
+    rows: Vec<(String, Status)>,
+}
+
+impl Summary {
+    pub fn succeeded(&mut self, name: impl Into<String>, details: impl Into<String>) {
+        self.rows
+            .push((name.into(), Status::Succeeded(details.into())));
+    }
+
+    pub fn skipped(&mut self, name: impl Into<String>, reason: impl Into<String>) {
+        self.rows
+            .push((name.into(), Status::Skipped(reason.into())));
+    }
+
+    pub fn failed(&mut self, name: impl Into<String>, reason: impl Into<String>) {
+        self.rows.push((name.into(), Status::Failed(reason.into())));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn failures(&self) -> usize {
+        self.rows
+            .iter()
+            .filter(|(_, s)| matches!(s, Status::Failed(_)))
+            .count()
+    }
+
+    pub fn print(&mut self) {
+        self.rows.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let (mut succeeded, mut skipped, mut failed) = (0, 0, 0);
+        let mut output = Table::new();
+        output.set_format(*format::consts::FORMAT_CLEAN);
+        output.set_titles(row![b -> "Mod", b -> "Status", b -> "Details"]);
+        for (name, status) in &self.rows {
+            match status {
+                Status::Succeeded(details) => {
+                    succeeded += 1;
+                    output.add_row(row![name, Fg -> "succeeded", details]);
+                }
+                Status::Skipped(reason) => {
+                    skipped += 1;
+                    output.add_row(row![name, Fy -> "skipped", reason]);
+                }
+                Status::Failed(reason) => {
+                    failed += 1;
+                    output.add_row(row![name, Fr -> "failed", reason]);
+                }
+            }
+        }
+        println!();
+        output.printstd();
+        println!("{succeeded} succeeded, {skipped} skipped, {failed} failed");
+    }
+
+    /// Returns an error if any mod failed.
+    pub fn into_result(self) -> CliResult {
+        match self.failures() {
+            0 => Ok(()),
+            n => Err(format!("{n} of {} mods failed", self.rows.len()).into()),
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use modio::types::files::File;
+use modio::types::id::FileId;
+use reqwest::Client;
+
+use crate::download;
+use crate::errors::Error;
+use crate::Result;
+
+/// Content-addressed store of downloaded modfiles.
+///
+/// Every archive is stored as `<root>/<file id>-<md5>/<filename>`, so projects on the same
+/// machine share identical modfiles instead of downloading them again.
+#[derive(Debug)]
+pub struct Cache {
+    root: PathBuf,
+}
+
+#[derive(Debug)]
+pub struct Entry {
+    pub file_id: FileId,
+    pub md5: String,
+    pub filename: String,
+    pub path: PathBuf,
+    pub size: u64,
+    /// Time the entry was added to the cache or last used by an install.
+    pub last_used: SystemTime,
+}
+
+impl Cache {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn entry_dir(&self, file_id: FileId, md5: &str) -> PathBuf {
+        self.root
+            .join(format!("{file_id}-{}", md5.to_ascii_lowercase()))
+    }
+
+    /// Returns the path of the archive in the cache, `None` if the checksum or the filename
+    /// would leave the entry directory.
+    fn entry_path(&self, file_id: FileId, md5: &str, filename: &str) -> Option<PathBuf> {
+        check_entry(md5, filename).ok()?;
+        Some(self.entry_dir(file_id, md5).join(filename))
+    }
+
+    /// Returns the path of the cached archive for the given modfile id and checksum.
+    pub fn get(&self, file_id: FileId, md5: &str, filename: &str) -> Option<PathBuf> {
+        let path = self.entry_path(file_id, md5, filename)?;
+        if path.is_file() {
+            touch(&path);
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if the archive is cached without marking it as used.
+    pub fn contains(&self, file_id: FileId, md5: &str, filename: &str) -> bool {
+        self.path(file_id, md5, filename).is_some()
+    }
+
+    /// Returns the path of the cached archive without marking it as used, e.g. to read from a
+    /// shared mirror.
+    pub fn path(&self, file_id: FileId, md5: &str, filename: &str) -> Option<PathBuf> {
+        self.entry_path(file_id, md5, filename)
+            .filter(|path| path.is_file())
+    }
+
+    /// Returns the path of the cached archive for the modfile and downloads it if necessary.
+    pub async fn fetch(&self, client: &Client, file: &File) -> Result<PathBuf> {
+        check_entry(&file.filehash.md5, &file.filename)?;
+        if let Some(path) = self.get(file.id, &file.filehash.md5, &file.filename) {
+            return Ok(path);
+        }
+        let dir = self.entry_dir(file.id, &file.filehash.md5);
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join(&file.filename);
+        download::save_to_file(client, file, &path).await?;
+        Ok(path)
+    }
+
+    /// Copy the archive of the modfile from another cache, e.g. a shared mirror. The copy is only
+    /// added after its size and MD5 checksum match. Returns `None` if `from` doesn't have the
+    /// archive. The archive of `from` isn't marked as used.
+    pub async fn import(
+        &self,
+        from: &Cache,
+        file_id: FileId,
+        filename: &str,
+        size: u64,
+        md5: &str,
+    ) -> Result<Option<PathBuf>> {
+        check_entry(md5, filename)?;
+        let Some(src) = from.path(file_id, md5, filename) else {
+            return Ok(None);
+        };
+        let dir = self.entry_dir(file_id, md5);
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join(filename);
+        let part = download::part_path(&path);
+        fs::copy(src, &part)?;
+        if let Err(e) = download::verify_checksum(&part, filename, size, md5).await {
+            fs::remove_file(&part)?;
+            return Err(e);
+        }
+        fs::rename(&part, &path)?;
+        Ok(Some(path))
+    }
+
+    /// List all complete entries of the cache.
+    pub fn entries(&self) -> io::Result<Vec<Entry>> {
+        let dirs = match fs::read_dir(&self.root) {
+            Ok(dirs) => dirs,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+        let mut entries = vec![];
+        for dir in dirs {
+            let dir = dir?;
+            let name = dir.file_name();
+            let Some((file_id, md5)) = name.to_str().and_then(parse_entry_name) else {
+                continue;
+            };
+            if !dir.file_type()?.is_dir() {
+                continue;
+            }
+            for file in fs::read_dir(dir.path())? {
+                let file = file?;
+                let md = file.metadata()?;
+                let filename = file.file_name().to_string_lossy().into_owned();
+                if !md.is_file() || filename.ends_with(".part") {
+                    continue;
+                }
+                entries.push(Entry {
+                    file_id,
+                    md5: md5.to_owned(),
+                    filename,
+                    path: file.path(),
+                    size: md.len(),
+                    last_used: md.modified()?,
+                });
+            }
+        }
+        entries.sort_by_key(|e| e.last_used);
+        Ok(entries)
+    }
+
+    /// Remove a single entry from the cache.
+    pub fn remove(&self, entry: &Entry) -> io::Result<()> {
+        match entry.path.parent() {
+            Some(dir) if dir.starts_with(&self.root) => fs::remove_dir_all(dir),
+            _ => fs::remove_file(&entry.path),
+        }
+    }
+
+    /// Remove everything from the cache, including incomplete downloads.
+    pub fn clean(&self) -> io::Result<()> {
+        match fs::remove_dir_all(&self.root) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remove entries not used within `max_age` and then the least recently used entries
+    /// until the cache is not larger than `max_size`.
+    ///
+    /// Returns the removed entries.
+    pub fn prune(
+        &self,
+        max_size: Option<u64>,
+        max_age: Option<Duration>,
+    ) -> io::Result<Vec<Entry>> {
+        let now = SystemTime::now();
+        let mut entries = self.entries()?;
+        let mut total = entries.iter().map(|e| e.size).sum::<u64>();
+        let mut removed = vec![];
+
+        // Entries are sorted by their last use, the oldest entries come first.
+        while let Some(entry) = entries.first() {
+            let expired = max_age.is_some_and(|max_age| {
+                let age = now.duration_since(entry.last_used).unwrap_or_default();
+                age > max_age
+            });
+            let too_large = max_size.is_some_and(|max_size| total > max_size);
+            if !expired && !too_large {
+                break;
+            }
+            let entry = entries.remove(0);
+            self.remove(&entry)?;
+            total -= entry.size;
+            removed.push(entry);
+        }
+        Ok(removed)
+    }
+}
+
+/// Refuse filenames of modfiles that aren't plain file names, e.g. `../x` or `a/b.zip`. The
+/// filenames are reported by the API and joined into the paths of the cache and mirrors.
+pub fn check_filename(filename: &str) -> std::result::Result<(), Error> {
+    let plain = !filename.contains(['/', '\\'])
+        && Path::new(filename).file_name() == Some(OsStr::new(filename));
+    if plain {
+        Ok(())
+    } else {
+        Err(Error::integrity(format!(
+            "refusing the modfile `{filename}`, the filename is not a plain file name"
+        )))
+    }
+}
+
+fn check_entry(md5: &str, filename: &str) -> std::result::Result<(), Error> {
+    if md5.is_empty() || !md5.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(Error::integrity(format!(
+            "refusing the modfile `{filename}`, the checksum `{md5}` is not a MD5 hash"
+        )));
+    }
+    check_filename(filename)
+}
+
+fn parse_entry_name(name: &str) -> Option<(FileId, &str)> {
+    let (id, md5) = name.split_once('-')?;
+    let id = id.parse::<u64>().ok().filter(|id| *id > 0)?;
+    Some((FileId::new(id), md5))
+}
+
+/// Mark a cached archive as used by updating its modification time.
+fn touch(path: &Path) {
+    if let Ok(file) = fs::File::options().write(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_name() {
+        assert_eq!(
+            parse_entry_name("12-a1b2c3"),
+            Some((FileId::new(12), "a1b2c3"))
+        );
+        assert_eq!(parse_entry_name("0-a1b2c3"), None);
+        assert_eq!(parse_entry_name("abc-a1b2c3"), None);
+        assert_eq!(parse_entry_name("a1b2c3"), None);
+    }
+
+    #[test]
+    fn plain_filenames() {
+        assert!(check_filename("mod.zip").is_ok());
+        assert!(check_filename("mod..v2.zip").is_ok());
+        for name in ["", ".", "..", "../../x", "a/b.zip", "a\\b.zip", "/tmp/x"] {
+            assert!(check_filename(name).is_err(), "{}", name);
+        }
+        let cache = Cache::new(PathBuf::from("cache"));
+        assert!(!cache.contains(FileId::new(1), "abc", "../mod.zip"));
+        assert!(cache
+            .entry_path(FileId::new(1), "../abc", "mod.zip")
+            .is_none());
+    }
+}
@@ -8,6 +8,10 @@ use serde::{Deserialize, Serialize};
 
 use modio::auth::Credentials;
 
+use crate::cache::Cache;
+use crate::errors::Error;
+use crate::policy::{Maturity, ModLists, Policy};
+use crate::retry::RetryPolicy;
 use crate::Result;
 
 #[derive(Debug)]
@@ -15,6 +19,43 @@ pub struct Config {
     cwd: PathBuf,
     home_dir: PathBuf,
     test_env: bool,
+    retry: RetryPolicy,
+    jobs: usize,
+    policy: Policy,
+    mirror: Option<PathBuf>,
+}
+
+/// Settings of `~/.modio/config.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct TomlSettings {
+    #[serde(default)]
+    net: TomlNet,
+    #[serde(default)]
+    policy: TomlPolicy,
+    #[serde(default)]
+    source: TomlSource,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct TomlNet {
+    retry: Option<u32>,
+    jobs: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct TomlSource {
+    mirror: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct TomlPolicy {
+    maturity: Option<Vec<Maturity>>,
+    #[serde(flatten)]
+    lists: ModLists,
 }
 
 #[derive(Debug, Default, Deserialize, Serialize)]
@@ -36,6 +77,10 @@ impl Config {
             cwd,
             home_dir,
             test_env: false,
+            retry: RetryPolicy::default(),
+            jobs: 4,
+            policy: Policy::default(),
+            mirror: None,
         }
     }
 
@@ -46,8 +91,21 @@ impl Config {
         Ok(Config::new(cwd, homedir.join(".modio")))
     }
 
-    pub fn configure(&mut self, test_env: bool) -> Result<()> {
+    pub fn configure(&mut self, test_env: bool, retry: Option<u32>) -> Result<()> {
+        let settings = self.load_settings()?;
+
         self.test_env = test_env;
+        if let Some(retry) = retry.or(settings.net.retry) {
+            self.retry = RetryPolicy::new(retry);
+        }
+        if let Some(jobs) = settings.net.jobs {
+            if jobs == 0 {
+                return Err(Error::usage("`net.jobs` must be at least 1").into());
+            }
+            self.jobs = jobs;
+        }
+        self.mirror = settings.source.mirror;
+        self.policy = Policy::new(settings.policy.maturity.as_deref(), &settings.policy.lists);
         Ok(())
     }
 
@@ -59,6 +117,10 @@ impl Config {
         &self.cwd
     }
 
+    pub fn cache(&self) -> Cache {
+        Cache::new(self.home_dir.join("cache"))
+    }
+
     pub fn host(&self) -> &str {
         if self.test_env {
             "https://api.test.mod.io/v1"
@@ -67,6 +129,27 @@ impl Config {
         }
     }
 
+    /// Retry policy for API `GET` requests and downloads.
+    pub fn retry(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    /// Number of parallel downloads.
+    pub fn jobs(&self) -> usize {
+        self.jobs
+    }
+
+    /// The mirror directory modfiles are copied from before they're downloaded. The mirror has
+    /// the layout of the download cache.
+    pub fn mirror(&self) -> Option<Cache> {
+        self.mirror.clone().map(Cache::new)
+    }
+
+    /// Content policy for the mods that are searched and installed.
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
     pub fn auth_token(&self) -> Result<Option<Credentials>> {
         let config = self.load_config()?;
         if let Some(creds) = config.hosts.get(self.host()) {
@@ -82,6 +165,16 @@ impl Config {
         }
     }
 
+    fn load_settings(&self) -> Result<TomlSettings> {
+        let path = self.home_dir.join("config.toml");
+        match fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content).map_err(|e| {
+                Error::usage(format!("failed to parse `{}`: {e}", path.display())).into()
+            }),
+            Err(_) => Ok(TomlSettings::default()),
+        }
+    }
+
     fn load_config(&self) -> Result<TomlConfig> {
         fs::create_dir_all(&self.home_dir)?;
 
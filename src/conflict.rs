@@ -0,0 +1,125 @@
+//! Detecting mods that install the same files.
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Two mods that install the same files.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Conflict {
+    /// The mod whose files are installed.
+    pub winner: String,
+    pub loser: String,
+    pub paths: Vec<String>,
+    /// True if the winner has a higher priority. Conflicts between mods with the same
+    /// priority are decided by the name of the mods.
+    pub resolved: bool,
+}
+
+/// The mod that installs each file.
+#[derive(Debug, Default)]
+pub struct Ownership {
+    owners: HashMap<String, usize>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Ownership {
+    /// Assign each path to the mod with the highest priority. Ties are won by the mod whose
+    /// name sorts last.
+    pub fn new<'a, I, P>(mods: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, i32, P)>,
+        P: IntoIterator<Item = &'a str>,
+    {
+        let mut mods = mods
+            .into_iter()
+            .enumerate()
+            .map(|(idx, (name, priority, paths))| (idx, name, priority, paths))
+            .collect::<Vec<_>>();
+        mods.sort_by(|a, b| (a.2, a.1).cmp(&(b.2, b.1)));
+
+        let mut claims = HashMap::<&str, Vec<usize>>::new();
+        let mut ranks = HashMap::new();
+        let mut names = HashMap::new();
+        for (rank, (idx, name, priority, paths)) in mods.into_iter().enumerate() {
+            ranks.insert(idx, (rank, priority));
+            names.insert(idx, name);
+            for path in paths {
+                claims.entry(path).or_default().push(idx);
+            }
+        }
+
+        let mut owners = HashMap::new();
+        let mut pairs = BTreeMap::<(usize, usize), Vec<String>>::new();
+        for (path, mut idxs) in claims {
+            idxs.sort_by_key(|idx| ranks[idx].0);
+            idxs.dedup();
+            let winner = *idxs.last().expect("at least one claim");
+            owners.insert(path.to_owned(), winner);
+            for loser in &idxs[..idxs.len() - 1] {
+                pairs
+                    .entry((winner, *loser))
+                    .or_default()
+                    .push(path.to_owned());
+            }
+        }
+
+        let mut conflicts = pairs
+            .into_iter()
+            .map(|((winner, loser), mut paths)| {
+                paths.sort();
+                Conflict {
+                    winner: names[&winner].to_owned(),
+                    loser: names[&loser].to_owned(),
+                    paths,
+                    resolved: ranks[&winner].1 > ranks[&loser].1,
+                }
+            })
+            .collect::<Vec<_>>();
+        conflicts.sort_by(|a, b| (&a.loser, &a.winner).cmp(&(&b.loser, &b.winner)));
+        Self { owners, conflicts }
+    }
+
+    /// Returns the index of the mod that installs the file.
+    pub fn owner(&self, path: &str) -> Option<usize> {
+        self.owners.get(path).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ownership() {
+        let mods = [
+            ("a", 0, vec!["common.pak", "a.pak"]),
+            ("b", 0, vec!["common.pak", "shared/x.dds"]),
+            ("c", 5, vec!["shared/x.dds", "c.pak"]),
+        ];
+        let ownership = Ownership::new(
+            mods.iter()
+                .map(|(n, p, paths)| (*n, *p, paths.iter().copied())),
+        );
+        assert_eq!(ownership.owner("a.pak"), Some(0));
+        assert_eq!(ownership.owner("common.pak"), Some(1));
+        assert_eq!(ownership.owner("shared/x.dds"), Some(2));
+        assert_eq!(ownership.owner("other.pak"), None);
+
+        assert_eq!(
+            ownership.conflicts,
+            [
+                Conflict {
+                    winner: "b".to_string(),
+                    loser: "a".to_string(),
+                    paths: vec!["common.pak".to_string()],
+                    resolved: false,
+                },
+                Conflict {
+                    winner: "c".to_string(),
+                    loser: "b".to_string(),
+                    paths: vec!["shared/x.dds".to_string()],
+                    resolved: true,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+use futures::{Stream, TryStreamExt};
+use modio::types::files::File;
+use reqwest::header::RANGE;
+use reqwest::{Client, RequestBuilder, StatusCode, Url};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::{AsyncWriteExt, BufWriter};
+
+use crate::errors::Error;
+use crate::md5;
+use crate::retry::RateLimited;
+use crate::Result;
+
+/// Returns the path of the partial download for `path` by appending `.part` to the filename.
+pub fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+/// Download the modfile to `out`.
+///
+/// The data is written to `<out>.part` first. If a partial file already exists, the download is
+/// resumed with a HTTP range request. The partial file is only renamed to `out` after its size
+/// and MD5 checksum match the values of the modfile.
+pub async fn save_to_file(client: &Client, file: &File, out: &Path) -> Result<()> {
+    let part = part_path(out);
+
+    let mut offset = match fs::metadata(&part).await {
+        Ok(md) if md.is_file() => md.len(),
+        Ok(_) | Err(_) => 0,
+    };
+    if offset > file.filesize {
+        fs::remove_file(&part).await?;
+        offset = 0;
+    }
+
+    if offset == 0 || offset < file.filesize {
+        let resp = request(client, file.download.binary_url.clone(), offset)
+            .send()
+            .await?;
+        if let Some(limited) = RateLimited::from_response(resp.status(), resp.headers()) {
+            return Err(limited.into());
+        }
+        let resp = resp.error_for_status()?;
+        write_body(&part, resp.status(), resp.bytes_stream()).await?;
+    }
+
+    let md5 = &file.filehash.md5;
+    finish(&part, out, &file.filename, file.filesize, md5).await
+}
+
+/// Returns the download request, resuming at `offset` with a range request.
+fn request(client: &Client, url: Url, offset: u64) -> RequestBuilder {
+    let req = client.get(url);
+    if offset > 0 {
+        req.header(RANGE, format!("bytes={offset}-"))
+    } else {
+        req
+    }
+}
+
+/// Write the response body to the partial file. Only a `206 Partial Content` response is
+/// appended, servers that don't support range requests answer with the full content.
+async fn write_body<S, E>(part: &Path, status: StatusCode, body: S) -> Result<()>
+where
+    S: Stream<Item = std::result::Result<Bytes, E>>,
+    E: std::error::Error + 'static,
+{
+    let mut opts = OpenOptions::new();
+    if status == StatusCode::PARTIAL_CONTENT {
+        opts.append(true);
+    } else {
+        opts.write(true).truncate(true);
+    }
+    let out = opts.create(true).open(part).await?;
+    let mut out = BufWriter::with_capacity(512 * 512, out);
+
+    futures::pin_mut!(body);
+    while let Some(bytes) = body.try_next().await? {
+        out.write_all(&bytes).await?;
+    }
+    out.flush().await?;
+    Ok(())
+}
+
+/// Rename the partial file to `out` if its size and checksum match, otherwise remove it so the
+/// next attempt starts over.
+async fn finish(part: &Path, out: &Path, filename: &str, size: u64, md5: &str) -> Result<()> {
+    if let Err(e) = verify_checksum(part, filename, size, md5).await {
+        fs::remove_file(part).await?;
+        return Err(e);
+    }
+    fs::rename(part, out).await?;
+    Ok(())
+}
+
+/// Check the size and MD5 checksum of `path` against the modfile.
+pub async fn verify(path: &Path, file: &File) -> Result<()> {
+    verify_checksum(path, &file.filename, file.filesize, &file.filehash.md5).await
+}
+
+/// Check the size and MD5 checksum of `path` against the expected values.
+pub async fn verify_checksum(path: &Path, filename: &str, size: u64, md5: &str) -> Result<()> {
+    let actual = fs::metadata(path).await?.len();
+    if actual != size {
+        return Err(Error::integrity(format!(
+            "size mismatch for `{filename}`: expected {size} bytes, got {actual} bytes",
+        ))
+        .into());
+    }
+    let checksum = md5::file_hash(path).await?;
+    if !checksum.eq_ignore_ascii_case(md5) {
+        return Err(Error::integrity(format!(
+            "checksum mismatch for `{filename}`: expected {md5}, got {checksum}",
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::TempDir;
+
+    #[test]
+    fn part_path_appends_suffix() {
+        assert_eq!(
+            part_path(Path::new("mods/mod.zip")),
+            PathBuf::from("mods/mod.zip.part")
+        );
+        assert_eq!(part_path(Path::new("mod")), PathBuf::from("mod.part"));
+    }
+
+    #[test]
+    fn range_request() {
+        let client = Client::new();
+        let url = Url::parse("https://example.com/mod.zip").unwrap();
+        let req = request(&client, url.clone(), 0).build().unwrap();
+        assert!(req.headers().get(RANGE).is_none());
+        let req = request(&client, url, 1024).build().unwrap();
+        assert_eq!(req.headers()[RANGE], "bytes=1024-");
+    }
+
+    fn body(data: &'static str) -> impl Stream<Item = std::io::Result<Bytes>> {
+        futures::stream::iter(vec![Ok(Bytes::from_static(data.as_bytes()))])
+    }
+
+    #[test]
+    fn resume_and_finish() {
+        let dir = TempDir::new("modiom-download").unwrap();
+        let out = dir.path().join("mod.zip");
+        let part = part_path(&out);
+        let rt = tokio::runtime::Runtime::new().unwrap();
+
+        // A range response is appended to the partial file.
+        std::fs::write(&part, "hello ").unwrap();
+        let status = StatusCode::PARTIAL_CONTENT;
+        rt.block_on(write_body(&part, status, body("world")))
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&part).unwrap(), "hello world");
+
+        // The full content replaces the partial file if the server ignores the range.
+        std::fs::write(&part, "hello ").unwrap();
+        rt.block_on(write_body(&part, StatusCode::OK, body("hello world")))
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&part).unwrap(), "hello world");
+
+        // The partial file is removed if the checksum doesn't match.
+        let md5 = "5eb63bbbe01eeed093cb22bb8f5acdc3";
+        let err = rt.block_on(finish(&part, &out, "mod.zip", 11, &"0".repeat(32)));
+        assert!(err.is_err());
+        assert!(!part.exists() && !out.exists());
+
+        std::fs::write(&part, "hello world").unwrap();
+        rt.block_on(finish(&part, &out, "mod.zip", 11, md5))
+            .unwrap();
+        assert!(!part.exists());
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "hello world");
+    }
+}
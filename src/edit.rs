@@ -0,0 +1,412 @@
+//! Editing `Modio.toml` while preserving comments and formatting.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml_edit::{value, DocumentMut, InlineTable, Item, Table, TableLike, Value};
+
+use crate::errors::Error;
+use crate::manifest::{Identifier, ModDependency};
+use crate::utils;
+use crate::Result;
+
+pub struct ManifestEditor {
+    path: PathBuf,
+    doc: DocumentMut,
+}
+
+impl ManifestEditor {
+    pub fn open(path: &Path) -> Result<Self> {
+        let content = utils::read(path)?;
+        let doc = content.parse::<DocumentMut>().map_err(|e| {
+            Error::usage(format!(
+                "failed to parse manifest at `{}`: {e}",
+                path.display()
+            ))
+        })?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            doc,
+        })
+    }
+
+    pub fn contains_mod(&self, name: &str) -> bool {
+        self.mods().is_some_and(|mods| mods.contains_key(name))
+    }
+
+    /// Insert or update the mod `name` in the `[mods]` table.
+    ///
+    /// Existing entries are updated key by key, so comments, unknown keys and the formatting of
+    /// unchanged values are kept and `[mods.name]` tables aren't turned into inline tables.
+    pub fn insert_mod(&mut self, name: &str, dep: &ModDependency) -> Result<()> {
+        let mods = self
+            .doc
+            .entry("mods")
+            .or_insert_with(|| Item::Table(Table::new()))
+            .as_table_like_mut()
+            .ok_or_else(|| Error::usage("`mods` in Modio.toml is not a table"))?;
+
+        let new = to_value(dep);
+        match mods.get_mut(name) {
+            Some(Item::Table(table)) => update_keys(table, &new),
+            Some(Item::Value(Value::InlineTable(table))) => update_inline_keys(table, &new),
+            Some(Item::Value(old)) => set_value(old, new),
+            Some(item) => *item = Item::Value(new),
+            None => {
+                mods.insert(name, Item::Value(new));
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the mod `name` from the `[mods]` table and the references to it in the profiles
+    /// and features. Returns the dotted paths of the removed references or `None` if the mod
+    /// doesn't exist.
+    pub fn remove_mod(&mut self, name: &str) -> Option<Vec<String>> {
+        self.doc
+            .get_mut("mods")
+            .and_then(Item::as_table_like_mut)
+            .and_then(|mods| mods.remove(name))?;
+
+        let mut refs = vec![];
+        let profiles = self
+            .doc
+            .get_mut("profile")
+            .and_then(Item::as_table_like_mut);
+        // Features may refer to a mod of the same name defined by a profile.
+        let mut profile_mod = false;
+        for (profile, p) in profiles.into_iter().flat_map(|p| p.iter_mut()) {
+            let Some(p) = p.as_table_like_mut() else {
+                continue;
+            };
+            profile_mod |= p
+                .get("mods")
+                .and_then(Item::as_table_like)
+                .is_some_and(|mods| mods.contains_key(name));
+            for key in ["include", "exclude"] {
+                if remove_from_array(p.get_mut(key), name) {
+                    refs.push(format!("profile.{profile}.{key}"));
+                }
+            }
+        }
+        if !profile_mod {
+            let features = self
+                .doc
+                .get_mut("features")
+                .and_then(Item::as_table_like_mut);
+            for (feature, members) in features.into_iter().flat_map(|f| f.iter_mut()) {
+                if remove_from_array(Some(members), name) {
+                    refs.push(format!("features.{feature}"));
+                }
+            }
+        }
+        Some(refs)
+    }
+
+    pub fn write(&self) -> Result<()> {
+        match fs::write(&self.path, self.doc.to_string()) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(format!("Failed to write {}: {}", self.path.display(), e).into()),
+        }
+    }
+
+    fn mods(&self) -> Option<&dyn toml_edit::TableLike> {
+        self.doc.get("mods").and_then(Item::as_table_like)
+    }
+}
+
+/// Returns the TOML value of a game or mod identifier.
+pub fn identifier(id: &Identifier) -> Value {
+    match id {
+        Identifier::Id(id) => Value::from(*id as i64),
+        Identifier::NameId(id) => Value::from(id.as_str()),
+    }
+}
+
+/// The keys of a mod entry written by [`to_value`].
+const MOD_KEYS: &[&str] = &[
+    "id",
+    "version",
+    "file",
+    "with-dependencies",
+    "priority",
+    "path",
+    "target",
+    "strip-components",
+    "include",
+    "exclude",
+];
+
+/// Update the keys of an existing mod entry to the keys of `new`.
+fn update_keys(table: &mut dyn TableLike, new: &Value) {
+    let entries = match new {
+        Value::InlineTable(t) => t.iter().collect::<Vec<_>>(),
+        id => vec![("id", id)],
+    };
+    for key in MOD_KEYS {
+        if !entries.iter().any(|(k, _)| k == key) {
+            table.remove(key);
+        }
+    }
+    for (key, v) in entries {
+        match table.get_mut(key) {
+            Some(Item::Value(old)) => set_value(old, v.clone()),
+            _ => {
+                table.insert(key, value(v.clone()));
+            }
+        }
+    }
+}
+
+/// Update the keys of an inline table and keep the whitespace before the closing brace after the
+/// last value, even if keys were added or removed at the end.
+fn update_inline_keys(table: &mut InlineTable, new: &Value) {
+    let last = table
+        .iter()
+        .last()
+        .and_then(|(k, v)| Some((k.to_owned(), v.decor().suffix()?.clone())));
+    update_keys(table, new);
+    let Some((key, suffix)) = last else {
+        return;
+    };
+    if let Some(v) = table.get_mut(&key) {
+        v.decor_mut().set_suffix("");
+    }
+    if let Some((_, v)) = table.iter_mut().last() {
+        v.decor_mut().set_suffix(suffix);
+    }
+}
+
+/// Replace the value unless it's unchanged and keep the comments and whitespace around it.
+fn set_value(old: &mut Value, new: Value) {
+    if same_value(old, &new) {
+        return;
+    }
+    let decor = old.decor().clone();
+    *old = new;
+    *old.decor_mut() = decor;
+}
+
+fn same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => a.value() == b.value(),
+        (Value::Integer(a), Value::Integer(b)) => a.value() == b.value(),
+        (Value::Boolean(a), Value::Boolean(b)) => a.value() == b.value(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| same_value(a, b))
+        }
+        _ => false,
+    }
+}
+
+/// Remove the string `name` from the array. Returns `true` if the array contained it.
+fn remove_from_array(item: Option<&mut Item>, name: &str) -> bool {
+    let Some(array) = item.and_then(Item::as_array_mut) else {
+        return false;
+    };
+    let len = array.len();
+    let prefix = array.get(0).and_then(|v| v.decor().prefix()).cloned();
+    array.retain(|v| v.as_str() != Some(name));
+    // The new first element takes the formatting of the removed one.
+    if let (Some(first), Some(prefix)) = (array.get_mut(0), prefix) {
+        first.decor_mut().set_prefix(prefix);
+    }
+    array.len() != len
+}
+
+fn to_value(dep: &ModDependency) -> Value {
+    match dep {
+        ModDependency::Simple(id) => identifier(id),
+        ModDependency::Detailed(dep) => {
+            let mut table = InlineTable::new();
+            if let Some(id) = &dep.id {
+                table.insert("id", identifier(id));
+            }
+            if let Some(version) = &dep.version {
+                table.insert("version", Value::from(version.as_str()));
+            }
+            if let Some(file) = dep.file {
+                table.insert("file", Value::from(i64::from(file)));
+            }
+            if let Some(with_deps) = dep.with_dependencies {
+                table.insert("with-dependencies", Value::from(with_deps));
+            }
+            if let Some(priority) = dep.priority {
+                table.insert("priority", Value::from(i64::from(priority)));
+            }
+            // The local source of mods without an id is written as `path` and their extract
+            // directory as `target`.
+            let extract = &dep.extract;
+            let (source, target) = match &dep.local {
+                Some(local) => (Some(local), extract.path.as_ref()),
+                None => (extract.path.as_ref(), None),
+            };
+            if let Some(path) = source {
+                table.insert("path", Value::from(path.as_str()));
+            }
+            if let Some(target) = target {
+                table.insert("target", Value::from(target.as_str()));
+            }
+            if let Some(n) = extract.strip_components {
+                table.insert("strip-components", Value::from(n as i64));
+            }
+            if let Some(include) = &extract.include {
+                table.insert(
+                    "include",
+                    Value::Array(include.iter().map(String::as_str).collect()),
+                );
+            }
+            if let Some(exclude) = &extract.exclude {
+                table.insert(
+                    "exclude",
+                    Value::Array(exclude.iter().map(String::as_str).collect()),
+                );
+            }
+            Value::InlineTable(table)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{DetailedModDependency, Extract};
+
+    fn editor(content: &str) -> ManifestEditor {
+        ManifestEditor {
+            path: PathBuf::from("Modio.toml"),
+            doc: content.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut editor = editor(
+            r#"# My mods
+[game]
+id = "gameone"
+
+[mods]
+# The best mod
+mod1 = 1 # pinned by id
+mod2 = "mod2"
+"#,
+        );
+        let dep = ModDependency::Detailed(DetailedModDependency {
+            id: Some(Identifier::NameId("mod3".to_string())),
+            with_dependencies: Some(true),
+            file: None,
+            version: Some("1.2".to_string()),
+            priority: None,
+            local: None,
+            extract: Extract::default(),
+        });
+        editor.insert_mod("mod3", &dep).unwrap();
+        assert_eq!(editor.remove_mod("mod2"), Some(vec![]));
+        assert_eq!(editor.remove_mod("mod4"), None);
+        assert!(editor.contains_mod("mod1"));
+
+        let expected = r#"# My mods
+[game]
+id = "gameone"
+
+[mods]
+# The best mod
+mod1 = 1 # pinned by id
+mod3 = { id = "mod3", version = "1.2", with-dependencies = true }
+"#;
+        assert_eq!(editor.doc.to_string(), expected);
+    }
+
+    #[test]
+    fn insert_without_mods_table() {
+        let mut editor = editor("[game]\nid = 1\n");
+        let dep = ModDependency::Simple(Identifier::Id(5));
+        editor.insert_mod("mod5", &dep).unwrap();
+        assert_eq!(
+            editor.doc.to_string(),
+            "[game]\nid = 1\n\n[mods]\nmod5 = 5\n"
+        );
+    }
+
+    #[test]
+    fn update_in_place() {
+        let mut editor = editor(
+            r#"[mods]
+mod1 = "mod-one" # latest
+mod2 = { id = 2, version = '1.0' } # pinned
+mod3 = { id = 3, version = '2.0' }
+
+# Texture pack
+[mods.texpack]
+id = "hd-textures" # by name-id
+# Install below mods/
+path = "mods"
+note = "kept"
+"#,
+        );
+        let detailed = |id: Option<Identifier>, version: Option<&str>, path: Option<&str>| {
+            ModDependency::Detailed(DetailedModDependency {
+                id,
+                with_dependencies: None,
+                file: None,
+                version: version.map(str::to_owned),
+                priority: None,
+                local: None,
+                extract: Extract {
+                    path: path.map(str::to_owned),
+                    ..Extract::default()
+                },
+            })
+        };
+        let mod1 = ModDependency::Simple(Identifier::NameId("mod-one-renamed".to_owned()));
+        editor.insert_mod("mod1", &mod1).unwrap();
+        let mod2 = detailed(Some(Identifier::Id(2)), Some("1.0"), Some("maps"));
+        editor.insert_mod("mod2", &mod2).unwrap();
+        let mod3 = detailed(Some(Identifier::Id(3)), None, Some("maps"));
+        editor.insert_mod("mod3", &mod3).unwrap();
+        let id = Identifier::NameId("hd-textures".to_owned());
+        let texpack = detailed(Some(id), Some("^2"), Some("mods"));
+        editor.insert_mod("texpack", &texpack).unwrap();
+
+        let expected = r#"[mods]
+mod1 = "mod-one-renamed" # latest
+mod2 = { id = 2, version = '1.0', path = "maps" } # pinned
+mod3 = { id = 3, path = "maps" }
+
+# Texture pack
+[mods.texpack]
+id = "hd-textures" # by name-id
+# Install below mods/
+path = "mods"
+note = "kept"
+version = "^2"
+"#;
+        assert_eq!(editor.doc.to_string(), expected);
+    }
+
+    #[test]
+    fn remove_references() {
+        let mut editor = editor(
+            r#"[mods]
+mod1 = 1
+mod2 = 2
+
+[features]
+extras = ["mod1", "mod2"] # optional
+
+[profile.server]
+exclude = ["mod1"]
+
+[profile.client.mods]
+mod2 = 3
+"#,
+        );
+        let refs = editor.remove_mod("mod1").unwrap();
+        assert_eq!(refs, ["profile.server.exclude", "features.extras"]);
+        // `mod2` of the client profile is still part of the feature.
+        assert_eq!(editor.remove_mod("mod2").unwrap(), Vec::<String>::new());
+        let doc = editor.doc.to_string();
+        assert!(doc.contains("extras = [\"mod2\"] # optional"), "{}", doc);
+    }
+}
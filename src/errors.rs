@@ -0,0 +1,174 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+
+use reqwest::StatusCode;
+
+/// The kind of an error and its exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Any other error. Exit code `1`.
+    Other,
+    /// Invalid command line arguments, manifest or config file. Exit code `2`.
+    Usage,
+    /// Missing, invalid or expired credentials. Exit code `3`.
+    Auth,
+    /// A game, mod or modfile doesn't exist. Exit code `4`.
+    NotFound,
+    /// Network errors and errors returned by the mod.io API. Exit code `5`.
+    Network,
+    /// A downloaded or installed file doesn't match its checksum or a modfile didn't pass the
+    /// virus scan. Exit code `6`.
+    Integrity,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::Usage => 2,
+            ErrorKind::Auth => 3,
+            ErrorKind::NotFound => 4,
+            ErrorKind::Network => 5,
+            ErrorKind::Integrity => 6,
+        }
+    }
+}
+
+/// An error message with an explicit kind.
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+    message: String,
+}
+
+impl Error {
+    pub fn new<S: Into<String>>(kind: ErrorKind, message: S) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    pub fn usage<S: Into<String>>(message: S) -> Self {
+        Self::new(ErrorKind::Usage, message)
+    }
+
+    pub fn auth<S: Into<String>>(message: S) -> Self {
+        Self::new(ErrorKind::Auth, message)
+    }
+
+    pub fn not_found<S: Into<String>>(message: S) -> Self {
+        Self::new(ErrorKind::NotFound, message)
+    }
+
+    pub fn integrity<S: Into<String>>(message: S) -> Self {
+        Self::new(ErrorKind::Integrity, message)
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+}
+
+impl StdError for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// The error returned by the commands.
+#[derive(Debug)]
+pub struct CliError {
+    kind: ErrorKind,
+    error: Box<dyn StdError>,
+}
+
+impl CliError {
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        self.kind.exit_code()
+    }
+}
+
+impl<E: Into<Box<dyn StdError>>> From<E> for CliError {
+    fn from(error: E) -> Self {
+        let error = error.into();
+        Self {
+            kind: kind_of(&*error),
+            error,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+/// Determine the kind of an error.
+pub fn kind_of(error: &(dyn StdError + 'static)) -> ErrorKind {
+    if let Some(e) = error.downcast_ref::<Error>() {
+        e.kind()
+    } else if let Some(e) = error.downcast_ref::<modio::Error>() {
+        modio_kind(e)
+    } else if let Some(e) = error.downcast_ref::<reqwest::Error>() {
+        match e.status() {
+            Some(StatusCode::NOT_FOUND) => ErrorKind::NotFound,
+            Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => ErrorKind::Auth,
+            _ => ErrorKind::Network,
+        }
+    } else if let Some(e) = error.downcast_ref::<io::Error>() {
+        match e.kind() {
+            io::ErrorKind::InvalidInput => ErrorKind::Usage,
+            _ => ErrorKind::Other,
+        }
+    } else if error.is::<toml::de::Error>() {
+        ErrorKind::Usage
+    } else {
+        ErrorKind::Other
+    }
+}
+
+fn modio_kind(e: &modio::Error) -> ErrorKind {
+    if e.is_auth() || e.is_terms_acceptance_required() {
+        ErrorKind::Auth
+    } else if e.is_validation() {
+        ErrorKind::Usage
+    } else if e.is_download() || e.status() == Some(StatusCode::NOT_FOUND) {
+        ErrorKind::NotFound
+    } else if e.is_builder() {
+        ErrorKind::Other
+    } else {
+        ErrorKind::Network
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds() {
+        let e = CliError::from("something failed");
+        assert_eq!(e.kind(), ErrorKind::Other);
+        assert_eq!(e.exit_code(), 1);
+
+        let e = CliError::from(Error::not_found("mod `foo` not found"));
+        assert_eq!(e.kind(), ErrorKind::NotFound);
+        assert_eq!(e.exit_code(), 4);
+        assert_eq!(e.to_string(), "mod `foo` not found");
+
+        let e = CliError::from(io::Error::new(io::ErrorKind::InvalidInput, "invalid"));
+        assert_eq!(e.kind(), ErrorKind::Usage);
+
+        let e = CliError::from(toml::from_str::<toml::Value>("a = ").unwrap_err());
+        assert_eq!(e.kind(), ErrorKind::Usage);
+    }
+}
@@ -0,0 +1,244 @@
+//! Extracting modfile archives into the install directory.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+
+use glob::Pattern;
+use md5::digest::Digest;
+use modio::types::id::ModId;
+use zip::read::ZipFile;
+use zip::ZipArchive;
+
+use crate::errors::Error;
+use crate::manifest::Extract;
+use crate::state::InstalledFile;
+use crate::utils;
+use crate::Result;
+
+/// The resolved extract options of a mod.
+#[derive(Debug)]
+pub struct ExtractOptions {
+    /// Target directory relative to the install directory.
+    pub dir: PathBuf,
+    pub strip_components: usize,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl ExtractOptions {
+    /// Resolve the options of the manifest by expanding the `{name}`, `{name_id}` and `{id}`
+    /// placeholders of the target path and parsing the glob patterns.
+    pub fn new(extract: &Extract, name: &str, name_id: &str, id: Option<ModId>) -> Result<Self> {
+        let dir = match &extract.path {
+            Some(path) => expand(path, name, name_id, id)?,
+            None => String::new(),
+        };
+        let dir = PathBuf::from(dir);
+        if dir
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(Error::usage(format!(
+                "mod `{name}`: path `{}` must be relative to the manifest directory",
+                dir.display()
+            ))
+            .into());
+        }
+        let patterns = |globs: &Option<Vec<String>>| {
+            globs
+                .iter()
+                .flatten()
+                .map(|g| {
+                    Pattern::new(g).map_err(|e| {
+                        Error::usage(format!("mod `{name}`: invalid glob pattern `{g}`: {e}"))
+                    })
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            dir,
+            strip_components: extract.strip_components.unwrap_or_default(),
+            include: patterns(&extract.include)?,
+            exclude: patterns(&extract.exclude)?,
+        })
+    }
+
+    /// Returns true if the entry path (after stripping components) is extracted.
+    pub fn matches(&self, path: &Path) -> bool {
+        let included = self.include.is_empty() || self.include.iter().any(|p| p.matches_path(path));
+        included && !self.exclude.iter().any(|p| p.matches_path(path))
+    }
+}
+
+fn expand(template: &str, name: &str, name_id: &str, id: Option<ModId>) -> Result<String> {
+    let path = utils::expand(template, |key| match key {
+        "name" => Some(name.to_owned()),
+        "name_id" => Some(name_id.to_owned()),
+        "id" => id.map(|id| id.to_string()),
+        _ => None,
+    })?;
+    Ok(path)
+}
+
+fn open(archive: &Path) -> Result<ZipArchive<fs::File>> {
+    ZipArchive::new(fs::File::open(archive)?).map_err(|e| {
+        Error::integrity(format!(
+            "failed to read archive `{}`: {e}",
+            archive.display()
+        ))
+        .into()
+    })
+}
+
+/// Returns the target path of the archive entry relative to the install directory or `None`
+/// if the entry isn't extracted.
+fn target_path(
+    archive: &Path,
+    entry: &ZipFile<'_>,
+    opts: &ExtractOptions,
+) -> Result<Option<PathBuf>> {
+    if entry.is_dir() {
+        return Ok(None);
+    }
+    let Some(path) = entry.enclosed_name() else {
+        return Err(Error::integrity(format!(
+            "archive `{}` contains the invalid path `{}`",
+            archive.display(),
+            entry.name()
+        ))
+        .into());
+    };
+    let path = path
+        .components()
+        .skip(opts.strip_components)
+        .collect::<PathBuf>();
+    if path.as_os_str().is_empty() || !opts.matches(&path) {
+        return Ok(None);
+    }
+    Ok(Some(opts.dir.join(path)))
+}
+
+/// Returns the paths of the files that are extracted from the archive.
+pub fn list(archive: &Path, opts: &ExtractOptions) -> Result<Vec<String>> {
+    let mut zip = open(archive)?;
+    let mut paths = vec![];
+    for i in 0..zip.len() {
+        let entry = zip.by_index_raw(i)?;
+        if let Some(path) = target_path(archive, &entry, opts)? {
+            paths.push(to_slash(&path));
+        }
+    }
+    Ok(paths)
+}
+
+/// Extract the archive into `root` and return the extracted files with their checksums.
+///
+/// Only the files for which `select` returns true are extracted.
+pub fn extract<F>(
+    archive: &Path,
+    root: &Path,
+    opts: &ExtractOptions,
+    select: F,
+) -> Result<Vec<InstalledFile>>
+where
+    F: Fn(&str) -> bool,
+{
+    let mut zip = open(archive)?;
+    let mut files = vec![];
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        let Some(path) = target_path(archive, &entry, opts)? else {
+            continue;
+        };
+        let rel = to_slash(&path);
+        if !select(&rel) {
+            continue;
+        }
+        let out = root.join(&path);
+        if let Some(parent) = out.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut writer = HashWriter {
+            inner: fs::File::create(&out)?,
+            md5: md5::Md5::default(),
+        };
+        let size = io::copy(&mut entry, &mut writer)?;
+        files.push(InstalledFile {
+            path: rel,
+            size,
+            md5: format!("{:x}", writer.md5.finalize()),
+        });
+    }
+    Ok(files)
+}
+
+/// Returns the path with `/` separators as it's stored in the state file.
+pub fn to_slash(path: &Path) -> String {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(c) => c.to_str(),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+struct HashWriter<W> {
+    inner: W,
+    md5: md5::Md5,
+}
+
+impl<W: Write> Write for HashWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.md5.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(extract: Extract) -> ExtractOptions {
+        ExtractOptions::new(&extract, "texpack", "hd-textures", Some(ModId::new(3))).unwrap()
+    }
+
+    #[test]
+    fn templates() {
+        let id = Some(ModId::new(3));
+        let path = expand("mods/{name_id}-{id}/{name}", "tex", "hd-tex", id).unwrap();
+        assert_eq!(path, "mods/hd-tex-3/tex");
+        assert!(expand("mods/{version}", "tex", "hd-tex", id).is_err());
+        assert!(expand("mods/{name", "tex", "hd-tex", id).is_err());
+        assert!(expand("mods/{id}", "tex", "hd-tex", None).is_err());
+
+        let extract = Extract {
+            path: Some("../{name_id}".to_string()),
+            ..Default::default()
+        };
+        assert!(ExtractOptions::new(&extract, "tex", "hd-tex", id).is_err());
+    }
+
+    #[test]
+    fn globs() {
+        let opts = options(Extract {
+            include: Some(vec!["*.pak".to_string(), "textures/**".to_string()]),
+            exclude: Some(vec!["**/*.psd".to_string()]),
+            ..Default::default()
+        });
+        assert!(opts.matches(Path::new("mod.pak")));
+        assert!(opts.matches(Path::new("textures/a/b.dds")));
+        assert!(!opts.matches(Path::new("textures/a/b.psd")));
+        assert!(!opts.matches(Path::new("readme.txt")));
+
+        let opts = options(Extract::default());
+        assert!(opts.matches(Path::new("readme.txt")));
+    }
+}
@@ -1,7 +1,23 @@
+pub mod cache;
 pub mod config;
+pub mod conflict;
+pub mod download;
+pub mod edit;
+pub mod errors;
+pub mod extract;
+pub mod loadorder;
+pub mod local;
+pub mod lockfile;
 pub mod manifest;
 pub mod md5;
+pub mod mirror;
+pub mod policy;
+pub mod retry;
+pub mod scan;
+pub mod state;
 pub mod utils;
+pub mod verify;
+pub mod version;
 
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
-pub type CliResult = std::result::Result<(), Box<dyn std::error::Error>>;
+pub type CliResult = std::result::Result<(), errors::CliError>;
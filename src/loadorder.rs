@@ -0,0 +1,167 @@
+//! The load order of the installed mods. Dependencies are loaded before the mods that depend
+//! on them.
+
+use std::collections::{BTreeSet, HashMap};
+
+use modio::types::id::ModId;
+use serde::Serialize;
+
+use crate::errors::{Error, ErrorKind};
+use crate::lockfile::LockedMod;
+use crate::manifest::{LoadOrder, LoadOrderFormat};
+use crate::utils;
+use crate::Result;
+
+/// A mod of the load order file.
+#[derive(Debug, Serialize)]
+pub struct Entry<'a> {
+    pub name: &'a str,
+    pub name_id: &'a str,
+    /// The mod id, `None` for mods installed from a local source.
+    pub id: Option<ModId>,
+    pub version: Option<&'a str>,
+    pub filename: &'a str,
+    /// The install path relative to the manifest: the extract directory or the copied archive.
+    pub path: String,
+}
+
+impl Entry<'_> {
+    fn value(&self, key: &str) -> Option<String> {
+        let value = match key {
+            "name" => self.name.to_owned(),
+            "name_id" => self.name_id.to_owned(),
+            "id" => self.id.map(|id| id.to_string()).unwrap_or_default(),
+            "version" => self.version.unwrap_or_default().to_owned(),
+            "filename" => self.filename.to_owned(),
+            "path" => self.path.clone(),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// Sort the mods so that each mod comes after its dependencies. Mods without an order between
+/// them are sorted by name. Dependencies that aren't part of `mods` are ignored.
+pub fn sort(mods: &[LockedMod]) -> Result<Vec<&LockedMod>> {
+    let index = mods
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, m)| Some((m.id?, idx)))
+        .collect::<HashMap<_, _>>();
+
+    let mut dependencies = vec![BTreeSet::new(); mods.len()];
+    let mut dependents = vec![vec![]; mods.len()];
+    for (idx, m) in mods.iter().enumerate() {
+        for dep in m.dependencies.iter().filter_map(|id| index.get(id)) {
+            if *dep != idx && dependencies[idx].insert(*dep) {
+                dependents[*dep].push(idx);
+            }
+        }
+    }
+
+    let mut pending = dependencies.iter().map(BTreeSet::len).collect::<Vec<_>>();
+    let mut ready = (0..mods.len())
+        .filter(|idx| pending[*idx] == 0)
+        .map(|idx| (&mods[idx].name, idx))
+        .collect::<BTreeSet<_>>();
+    let mut order = Vec::with_capacity(mods.len());
+    while let Some((_, idx)) = ready.pop_first() {
+        order.push(&mods[idx]);
+        for dependent in &dependents[idx] {
+            pending[*dependent] -= 1;
+            if pending[*dependent] == 0 {
+                ready.insert((&mods[*dependent].name, *dependent));
+            }
+        }
+    }
+    if order.len() == mods.len() {
+        return Ok(order);
+    }
+
+    // Every mod left has a dependency that is left, so following them leads into a cycle.
+    let left = |idx: &usize| pending[*idx] > 0;
+    let start = (0..mods.len())
+        .filter(left)
+        .min_by_key(|idx| &mods[*idx].name)
+        .expect("a mod is left");
+    let mut path = vec![start];
+    loop {
+        let last = path[path.len() - 1];
+        let next = dependencies[last]
+            .iter()
+            .copied()
+            .filter(left)
+            .min_by_key(|idx| &mods[*idx].name)
+            .expect("a dependency is left");
+        if let Some(pos) = path.iter().position(|idx| *idx == next) {
+            let cycle = path[pos..]
+                .iter()
+                .chain([&next])
+                .map(|idx| format!("`{}`", mods[*idx].name))
+                .collect::<Vec<_>>();
+            let msg = format!(
+                "cannot determine the load order, the mods depend on each other: {}",
+                cycle.join(" -> ")
+            );
+            return Err(Error::new(ErrorKind::Other, msg).into());
+        }
+        path.push(next);
+    }
+}
+
+/// Format the load order file.
+///
+/// The `lines` format writes one line per mod using the template with the placeholders
+/// `{name}`, `{name_id}`, `{id}`, `{version}`, `{filename}` and `{path}`.
+pub fn render(load_order: &LoadOrder, entries: &[Entry<'_>]) -> Result<String> {
+    match load_order.format {
+        LoadOrderFormat::Lines => {
+            let template = load_order.template.as_deref().unwrap_or("{name}");
+            let mut out = String::new();
+            for entry in entries {
+                out.push_str(&utils::expand(template, |key| entry.value(key))?);
+                out.push('\n');
+            }
+            Ok(out)
+        }
+        LoadOrderFormat::Json => {
+            let mut out = serde_json::to_string_pretty(entries)?;
+            out.push('\n');
+            Ok(out)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(mods: &[LockedMod]) -> Vec<&str> {
+        let order = sort(mods).unwrap();
+        order.into_iter().map(|m| m.name.as_str()).collect()
+    }
+
+    #[test]
+    fn topological_order() {
+        let mods = [
+            LockedMod::test("maps", 1, None, &[3, 4]),
+            LockedMod::test("zlib", 2, None, &[]),
+            LockedMod::test("core", 3, None, &[2]),
+            LockedMod::test("assets", 4, None, &[99]),
+            LockedMod::test("extra", 5, None, &[5]),
+        ];
+        assert_eq!(names(&mods), ["assets", "extra", "zlib", "core", "maps"]);
+    }
+
+    #[test]
+    fn cycles() {
+        let mods = [
+            LockedMod::test("a", 1, None, &[2]),
+            LockedMod::test("b", 2, None, &[3]),
+            LockedMod::test("c", 3, None, &[2]),
+            LockedMod::test("d", 4, None, &[]),
+        ];
+        let err = sort(&mods).unwrap_err();
+        assert!(err.to_string().ends_with("`b` -> `c` -> `b`"), "{}", err);
+    }
+}
@@ -0,0 +1,144 @@
+//! Mods installed from a local directory or zip archive instead of mod.io.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use zip::write::SimpleFileOptions;
+use zip::{DateTime, ZipWriter};
+
+use crate::cache::check_filename;
+use crate::errors::Error;
+use crate::extract::to_slash;
+use crate::lockfile::LockedMod;
+use crate::md5;
+use crate::Result;
+
+/// Returns the directory the local sources of the manifest directory are packed into.
+pub fn pack_dir(root: &Path) -> PathBuf {
+    root.join(".modiom").join("local")
+}
+
+/// Returns the archive of the local source relative to the manifest directory. Directories are
+/// packed into `<out>/<name>.zip`, without the `.modiom` directory of the manifest.
+pub fn archive(root: &Path, name: &str, source: &str, out: &Path) -> Result<PathBuf> {
+    // The key of the mod in the manifest names the archive.
+    let filename = format!("{name}.zip");
+    if check_filename(&filename).is_err() {
+        return Err(Error::usage(format!(
+            "mod `{name}`: the name of a mod with a local source must be a plain file name"
+        ))
+        .into());
+    }
+    let path = root.join(source);
+    let md = fs::metadata(&path).map_err(|e| {
+        Error::not_found(format!(
+            "mod `{name}`: local source `{}` not found: {e}",
+            path.display()
+        ))
+    })?;
+    if md.is_file() {
+        return Ok(path);
+    }
+    fs::create_dir_all(out)?;
+    let out = out.join(filename);
+    pack(&path, &out, &root.join(".modiom"))?;
+    Ok(out)
+}
+
+/// Pack the files below `dir` into a zip archive, skipping the directory `skip`. The entries are
+/// sorted and have a fixed modification time, so the checksum of the archive only changes with
+/// the content.
+pub fn pack(dir: &Path, out: &Path, skip: &Path) -> Result<()> {
+    let skip = fs::canonicalize(skip).ok();
+    let mut files = vec![];
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(d) = dirs.pop() {
+        for entry in fs::read_dir(&d)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                if skip.is_some() && fs::canonicalize(entry.path()).ok() == skip {
+                    continue;
+                }
+                dirs.push(entry.path());
+            } else {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+
+    let opts = SimpleFileOptions::default().last_modified_time(DateTime::default());
+    let mut zip = ZipWriter::new(fs::File::create(out)?);
+    for path in files {
+        let name = to_slash(path.strip_prefix(dir).unwrap_or(&path));
+        zip.start_file(name, opts)?;
+        io::copy(&mut fs::File::open(&path)?, &mut zip)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// Returns the lock entry for the archive of a local source. Local mods have no mod or modfile
+/// id and use the manifest key as name-id.
+pub async fn lock(name: &str, source: &str, archive: &Path) -> Result<LockedMod> {
+    let filename = match archive.file_name() {
+        Some(filename) => filename.to_string_lossy().into_owned(),
+        None => format!("{name}.zip"),
+    };
+    Ok(LockedMod {
+        name: name.to_owned(),
+        id: None,
+        name_id: name.to_owned(),
+        file: None,
+        filename,
+        version: None,
+        size: fs::metadata(archive)?.len(),
+        md5: md5::file_hash(archive).await?,
+        dependency_of: None,
+        dependencies: vec![],
+        local: Some(source.to_owned()),
+        maturity_option: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::{self, ExtractOptions};
+    use crate::manifest::Extract;
+    use crate::utils::TempDir;
+
+    #[test]
+    fn pack_directory() {
+        let tmp = TempDir::new("modiom-local").unwrap();
+        let root = tmp.path();
+        fs::create_dir_all(root.join("mymod/textures")).unwrap();
+        fs::write(root.join("mymod/mod.txt"), "mod").unwrap();
+        fs::write(root.join("mymod/textures/a.dds"), "dds").unwrap();
+
+        let archive = archive(root, "mymod", "mymod", &pack_dir(root)).unwrap();
+        assert_eq!(archive, root.join(".modiom/local/mymod.zip"));
+        let opts = ExtractOptions::new(&Extract::default(), "mymod", "mymod", None).unwrap();
+        let paths = extract::list(&archive, &opts).unwrap();
+        assert_eq!(paths, ["mod.txt", "textures/a.dds"]);
+
+        // Packing the same content again results in the same archive.
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let first = rt.block_on(md5::file_hash(&archive)).unwrap();
+        pack(&root.join("mymod"), &archive, &root.join(".modiom")).unwrap();
+        assert_eq!(rt.block_on(md5::file_hash(&archive)).unwrap(), first);
+
+        // The name of the mod can't leave the directory of the packed archives.
+        assert!(super::archive(root, "../x", "mymod", &pack_dir(root)).is_err());
+
+        // A source containing the manifest directory doesn't pack its own archives.
+        fs::write(root.join("manifest.txt"), "manifest").unwrap();
+        let all = super::archive(root, "all", ".", &pack_dir(root)).unwrap();
+        let paths = extract::list(&all, &opts).unwrap();
+        assert_eq!(
+            paths,
+            ["manifest.txt", "mymod/mod.txt", "mymod/textures/a.dds"]
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use modio::types::files::File;
+use modio::types::id::{FileId, GameId, ModId};
+use modio::types::mods::{MaturityOption, Mod};
+
+use crate::errors::Error;
+use crate::manifest::Identifier;
+use crate::utils;
+use crate::Result;
+
+const HEADER: &str = "\
+# This file is automatically generated by modiom.
+# It is not intended for manual editing.
+";
+
+/// The resolved modfiles of the last `modiom install`, stored as `Modio.lock` next to the
+/// manifest.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Lockfile {
+    pub game: Option<GameId>,
+    #[serde(rename = "mod", default)]
+    pub mods: Vec<LockedMod>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LockedMod {
+    /// Key of the mod in the `[mods]` table of the manifest.
+    pub name: String,
+    /// The mod id, `None` for mods installed from a local source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<ModId>,
+    pub name_id: String,
+    /// The modfile id, `None` for mods installed from a local source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<FileId>,
+    pub filename: String,
+    pub version: Option<String>,
+    pub size: u64,
+    pub md5: String,
+    /// Name of the mod that pulled in the dependency.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dependency_of: Option<String>,
+    /// The mods the mod depends on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<ModId>,
+    /// The local directory or zip archive the mod is installed from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local: Option<String>,
+    /// The maturity flags of the mod, see `MaturityOption`. `None` for mods installed from a
+    /// local source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maturity_option: Option<u8>,
+}
+
+impl LockedMod {
+    pub fn new(name: &str, m: &Mod, file: &File) -> Self {
+        Self {
+            name: name.to_owned(),
+            id: Some(m.id),
+            name_id: m.name_id.clone(),
+            file: Some(file.id),
+            filename: file.filename.clone(),
+            version: file.version.clone(),
+            size: file.filesize,
+            md5: file.filehash.md5.clone(),
+            dependency_of: None,
+            dependencies: vec![],
+            local: None,
+            maturity_option: Some(m.maturity_option.bits()),
+        }
+    }
+
+    /// Returns the recorded maturity flags of the mod.
+    pub fn maturity(&self) -> Option<MaturityOption> {
+        self.maturity_option.map(MaturityOption::from_bits_truncate)
+    }
+
+    /// Returns true if the locked mod is the mod referred to by `id`.
+    pub fn matches(&self, id: &Identifier) -> bool {
+        match id {
+            Identifier::Id(id) => self.id.is_some_and(|m| m.get() == *id),
+            Identifier::NameId(name_id) => self.name_id == *name_id,
+        }
+    }
+}
+
+#[cfg(test)]
+impl LockedMod {
+    /// Returns a lock entry of mod `id` with the modfile `id` for tests.
+    pub(crate) fn test(name: &str, id: u64, dependency_of: Option<&str>, deps: &[u64]) -> Self {
+        LockedMod {
+            name: name.to_owned(),
+            id: Some(ModId::new(id)),
+            name_id: name.to_owned(),
+            file: Some(FileId::new(id)),
+            filename: format!("{name}.zip"),
+            version: None,
+            size: 0,
+            md5: String::new(),
+            dependency_of: dependency_of.map(str::to_owned),
+            dependencies: deps.iter().map(|id| ModId::new(*id)).collect(),
+            local: None,
+            maturity_option: Some(0),
+        }
+    }
+}
+
+impl Lockfile {
+    pub fn get(&self, name: &str) -> Option<&LockedMod> {
+        self.mods.iter().find(|m| m.name == name)
+    }
+
+    /// Returns the locked dependencies of the selected mods. Dependencies are followed through
+    /// the mod that pulled them in and the recorded dependencies of each mod, so dependencies of
+    /// mods that aren't selected are left out.
+    pub fn dependencies_of<'a>(&'a self, selected: &[&'a LockedMod]) -> Vec<&'a LockedMod> {
+        let mut names = selected
+            .iter()
+            .map(|l| l.name.as_str())
+            .collect::<HashSet<_>>();
+        let mut ids = selected
+            .iter()
+            .flat_map(|l| l.dependencies.iter().copied())
+            .collect::<HashSet<_>>();
+        let mut deps = vec![];
+        loop {
+            let next = self
+                .mods
+                .iter()
+                .filter(|l| !names.contains(l.name.as_str()))
+                .filter(|l| match &l.dependency_of {
+                    Some(parent) => {
+                        names.contains(parent.as_str()) || l.id.is_some_and(|id| ids.contains(&id))
+                    }
+                    None => false,
+                })
+                .collect::<Vec<_>>();
+            if next.is_empty() {
+                return deps;
+            }
+            for l in next {
+                names.insert(&l.name);
+                ids.extend(l.dependencies.iter().copied());
+                deps.push(l);
+            }
+        }
+    }
+}
+
+/// Returns the path of the lockfile that belongs to the manifest at `manifest_path`.
+pub fn path_for(manifest_path: &Path) -> PathBuf {
+    manifest_path.with_file_name("Modio.lock")
+}
+
+/// Read the lockfile at `path`. Returns `None` if the file doesn't exist.
+pub fn read(path: &Path) -> Result<Option<Lockfile>> {
+    let content = match utils::read(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let lockfile = toml::from_str(&content).map_err(|e| {
+        Error::usage(format!(
+            "failed to parse lockfile at `{}`: {e}",
+            path.display()
+        ))
+    })?;
+    Ok(Some(lockfile))
+}
+
+pub fn write(path: &Path, lockfile: &Lockfile) -> Result<()> {
+    let content = toml::to_string(lockfile)?;
+    match fs::write(path, format!("{HEADER}\n{content}")) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(format!("Failed to write {}: {}", path.display(), e).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lockfile() {
+        let raw = r#"
+        game = 5
+
+        [[mod]]
+        name = "mod1"
+        id = 1
+        name-id = "mod-one"
+        file = 11
+        filename = "mod1.zip"
+        version = "1.0"
+        size = 1024
+        md5 = "2d4a0e2d7273db6b0a94b0740a88ad0d"
+        "#;
+
+        let lockfile: Lockfile = toml::from_str(raw).unwrap();
+        assert_eq!(lockfile.game, Some(GameId::new(5)));
+
+        let m = lockfile.get("mod1").unwrap();
+        assert_eq!(m.file, Some(FileId::new(11)));
+        assert!(m.matches(&Identifier::Id(1)));
+        assert!(m.matches(&Identifier::NameId("mod-one".to_string())));
+        assert!(!m.matches(&Identifier::NameId("mod1".to_string())));
+        assert!(lockfile.get("mod2").is_none());
+
+        let content = toml::to_string(&lockfile).unwrap();
+        assert_eq!(lockfile, toml::from_str(&content).unwrap());
+    }
+
+    #[test]
+    fn dependencies_of_selected_mods() {
+        let lock = Lockfile {
+            game: None,
+            mods: vec![
+                LockedMod::test("a", 1, None, &[10]),
+                LockedMod::test("b", 2, None, &[20, 10]),
+                LockedMod::test("dep-a", 10, Some("a"), &[11]),
+                LockedMod::test("dep-dep", 11, Some("dep-a"), &[]),
+                LockedMod::test("dep-b", 20, Some("b"), &[]),
+            ],
+        };
+        let deps = |selected: &[&str]| {
+            let selected = selected
+                .iter()
+                .map(|name| lock.get(name).unwrap())
+                .collect::<Vec<_>>();
+            let mut names = lock
+                .dependencies_of(&selected)
+                .into_iter()
+                .map(|l| l.name.as_str())
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+        assert_eq!(deps(&["a"]), ["dep-a", "dep-dep"]);
+        // `b` shares `dep-a`, which was pulled in by `a`.
+        assert_eq!(deps(&["b"]), ["dep-a", "dep-b", "dep-dep"]);
+        assert!(deps(&[]).is_empty());
+    }
+}
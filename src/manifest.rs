@@ -5,6 +5,8 @@ use std::path::Path;
 use serde::de;
 use serde::{Deserialize, Serialize};
 
+use crate::errors::Error;
+use crate::policy::ModLists;
 use crate::utils;
 
 pub type ModDependencies = BTreeMap<String, ModDependency>;
@@ -14,9 +16,100 @@ pub type ModDependencies = BTreeMap<String, ModDependency>;
 pub struct ModioManifest {
     pub game: Game,
     pub mods: Option<ModDependencies>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profile: BTreeMap<String, Profile>,
+    /// Groups of optional mods, e.g. `hd-textures = ["texpack", "lighting"]`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub features: BTreeMap<String, Vec<String>>,
+    /// The load order file written by `install`.
+    pub load_order: Option<LoadOrder>,
+    /// Mods that are allowed or blocked in addition to the policy of the config.
+    pub policy: Option<ModLists>,
 }
 
-#[derive(Debug, PartialEq, Eq, Serialize)]
+/// The features enabled for an install.
+#[derive(Debug, Clone, Copy)]
+pub enum Features<'a> {
+    Selected(&'a [String]),
+    All,
+}
+
+/// A named selection of the mods, e.g. `[profile.server]`.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Profile {
+    /// Only install these mods of the `[mods]` table.
+    pub include: Option<Vec<String>>,
+    /// Don't install these mods of the `[mods]` table.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Additional mods of the profile.
+    pub mods: Option<ModDependencies>,
+}
+
+impl ModioManifest {
+    /// Returns the mods to install for the profile or all mods of `[mods]` without a profile.
+    ///
+    /// Mods that are part of a feature are only installed if the feature is enabled.
+    pub fn select_mods(
+        &self,
+        profile: Option<&str>,
+        features: Features<'_>,
+    ) -> Result<ModDependencies, Error> {
+        let mut mods = self.mods.clone().unwrap_or_default();
+        if let Some(name) = profile {
+            let profile = self
+                .profile
+                .get(name)
+                .ok_or_else(|| Error::usage(format!("profile `{name}` is not defined")))?;
+
+            let names = profile.include.iter().flatten().chain(&profile.exclude);
+            if let Some(m) = names.into_iter().find(|m| !mods.contains_key(*m)) {
+                return Err(Error::usage(format!(
+                    "profile `{name}` refers to mod `{m}` which is not defined in `[mods]`"
+                )));
+            }
+            if let Some(include) = &profile.include {
+                mods.retain(|m, _| include.contains(m));
+            }
+            mods.retain(|m, _| !profile.exclude.contains(m));
+            mods.extend(profile.mods.clone().unwrap_or_default());
+        }
+
+        for (feature, members) in &self.features {
+            let defined = |m: &String| {
+                self.mods.iter().flatten().any(|(name, _)| name == m)
+                    || self
+                        .profile
+                        .values()
+                        .any(|p| p.mods.iter().flatten().any(|(name, _)| name == m))
+            };
+            if let Some(m) = members.iter().find(|m| !defined(m)) {
+                return Err(Error::usage(format!(
+                    "feature `{feature}` refers to mod `{m}` which is not defined"
+                )));
+            }
+        }
+        let enabled = match features {
+            Features::All => return Ok(mods),
+            Features::Selected(enabled) => enabled,
+        };
+        if let Some(f) = enabled.iter().find(|f| !self.features.contains_key(*f)) {
+            return Err(Error::usage(format!("feature `{f}` is not defined")));
+        }
+        mods.retain(|m, _| {
+            let mut groups = self
+                .features
+                .iter()
+                .filter(|(_, members)| members.contains(m))
+                .peekable();
+            groups.peek().is_none() || groups.any(|(f, _)| enabled.contains(f))
+        });
+        Ok(mods)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 #[serde(untagged)]
 pub enum Identifier {
     Id(u64),
@@ -30,6 +123,25 @@ pub struct Game {
     pub with_dependencies: Option<bool>,
 }
 
+impl From<&str> for Identifier {
+    /// Numeric strings are unique ids, everything else is a name-id.
+    fn from(s: &str) -> Self {
+        match s.parse() {
+            Ok(id) => Identifier::Id(id),
+            Err(_) => Identifier::NameId(s.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Identifier::Id(id) => id.fmt(f),
+            Identifier::NameId(id) => id.fmt(f),
+        }
+    }
+}
+
 // {{{ impl Deserialize for Identifier
 impl<'de> Deserialize<'de> for Identifier {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -72,7 +184,7 @@ impl<'de> Deserialize<'de> for Identifier {
 }
 // }}}
 
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 #[serde(untagged)]
 pub enum ModDependency {
     Simple(Identifier),
@@ -80,10 +192,11 @@ pub enum ModDependency {
 }
 
 impl ModDependency {
-    pub fn id(&self) -> &Identifier {
+    /// Returns the id of the mod on mod.io, `None` for mods with a local source.
+    pub fn id(&self) -> Option<&Identifier> {
         match *self {
-            ModDependency::Simple(ref id) => id,
-            ModDependency::Detailed(ref mod_) => &mod_.id,
+            ModDependency::Simple(ref id) => Some(id),
+            ModDependency::Detailed(ref mod_) => mod_.id.as_ref(),
         }
     }
 
@@ -100,6 +213,36 @@ impl ModDependency {
             ModDependency::Detailed(ref mod_) => mod_.version.as_ref(),
         }
     }
+
+    pub fn with_dependencies(&self) -> Option<bool> {
+        match *self {
+            ModDependency::Simple(_) => None,
+            ModDependency::Detailed(ref mod_) => mod_.with_dependencies,
+        }
+    }
+
+    pub fn priority(&self) -> i32 {
+        match *self {
+            ModDependency::Simple(_) => 0,
+            ModDependency::Detailed(ref mod_) => mod_.priority.unwrap_or_default(),
+        }
+    }
+
+    /// Returns the local directory or zip archive the mod is installed from.
+    pub fn local(&self) -> Option<&str> {
+        match *self {
+            ModDependency::Simple(_) => None,
+            ModDependency::Detailed(ref mod_) => mod_.local.as_deref(),
+        }
+    }
+
+    /// Returns the extract options if the modfile is extracted.
+    pub fn extract(&self) -> Option<&Extract> {
+        match *self {
+            ModDependency::Simple(_) => None,
+            ModDependency::Detailed(ref mod_) => Some(&mod_.extract).filter(|e| e.is_enabled()),
+        }
+    }
 }
 
 // {{{ impl Deserialize for ModDependency
@@ -142,8 +285,30 @@ impl<'de> serde::de::Deserialize<'de> for ModDependency {
             where
                 V: serde::de::MapAccess<'de>,
             {
+                #[derive(Deserialize)]
+                struct Raw {
+                    #[serde(flatten)]
+                    mod_: DetailedModDependency,
+                    target: Option<String>,
+                }
+
                 let mvd = serde::de::value::MapAccessDeserializer::new(map);
-                DetailedModDependency::deserialize(mvd).map(ModDependency::Detailed)
+                let Raw { mut mod_, target } = Raw::deserialize(mvd)?;
+                // Without an id, `path` is the local source and `target` the extract directory.
+                if mod_.id.is_none() {
+                    mod_.local = mod_.extract.path.take();
+                    mod_.extract.path = target;
+                    if mod_.local.is_none() {
+                        return Err(serde::de::Error::custom(
+                            "missing field `id`, or `path` for a local source",
+                        ));
+                    }
+                } else if target.is_some() {
+                    return Err(serde::de::Error::custom(
+                        "`target` is only used by mods with a local source, use `path` instead",
+                    ));
+                }
+                Ok(ModDependency::Detailed(mod_))
             }
         }
 
@@ -152,13 +317,83 @@ impl<'de> serde::de::Deserialize<'de> for ModDependency {
 }
 // }}}
 
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct DetailedModDependency {
-    pub id: Identifier,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Identifier>,
     pub with_dependencies: Option<bool>,
     pub file: Option<u32>,
     pub version: Option<String>,
+    /// Files of mods with a higher priority replace the files of other mods.
+    pub priority: Option<i32>,
+    /// Install the mod from a local directory or zip archive instead of mod.io, set by `path`
+    /// for mods without an id. Their extract directory is set by `target`.
+    #[serde(
+        rename = "path",
+        skip_deserializing,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub local: Option<String>,
+    #[serde(flatten)]
+    pub extract: Extract,
+}
+
+/// Options to extract the modfile instead of copying the archive.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Extract {
+    /// Target directory relative to the manifest, e.g. `"mods/{name_id}"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Number of leading path components removed from the archive entries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strip_components: Option<usize>,
+    /// Only extract the entries matching one of the glob patterns.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include: Option<Vec<String>>,
+    /// Don't extract the entries matching one of the glob patterns.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclude: Option<Vec<String>>,
+}
+
+impl Extract {
+    pub fn is_enabled(&self) -> bool {
+        self.path.is_some()
+            || self.strip_components.is_some()
+            || self.include.is_some()
+            || self.exclude.is_some()
+    }
+}
+
+/// The `[load-order]` section.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LoadOrder {
+    /// Path of the load order file relative to the manifest.
+    pub file: String,
+    #[serde(default)]
+    pub format: LoadOrderFormat,
+    /// Template of each line for the `lines` format, defaults to `"{name}"`.
+    pub template: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoadOrderFormat {
+    /// One line per mod.
+    #[default]
+    Lines,
+    /// A JSON array of the mods.
+    Json,
+}
+
+/// Returns the mods that are looked up on mod.io, i.e. without a local source.
+pub fn remote_mods(mods: &ModDependencies) -> ModDependencies {
+    mods.iter()
+        .filter(|(_, dep)| dep.local().is_none())
+        .map(|(name, dep)| (name.clone(), dep.clone()))
+        .collect()
 }
 
 pub fn read(path: &Path) -> Result<ModioManifest, Box<dyn std::error::Error>> {
@@ -167,11 +402,88 @@ pub fn read(path: &Path) -> Result<ModioManifest, Box<dyn std::error::Error>> {
 }
 
 pub fn parse(content: &str, path: &Path) -> Result<ModioManifest, Box<dyn std::error::Error>> {
-    let manifest = toml::from_str(content)
-        .map_err(|_| format!("failed to parse manifest at `{}`", path.display()))?;
+    let manifest = toml::from_str(content).map_err(|e| {
+        Error::usage(format!(
+            "failed to parse manifest at `{}`\n\n{e}",
+            path.display()
+        ))
+    })?;
     Ok(manifest)
 }
 
+/// Returns the dotted paths of the keys in the manifest that are not used by modiom.
+pub fn unknown_keys(content: &str) -> Vec<String> {
+    const TOP_LEVEL: &[&str] = &[
+        "game",
+        "mods",
+        "profile",
+        "features",
+        "load-order",
+        "policy",
+    ];
+    const GAME: &[&str] = &["id", "with-dependencies"];
+    const MOD: &[&str] = &[
+        "id",
+        "with-dependencies",
+        "file",
+        "version",
+        "priority",
+        "path",
+        "target",
+        "strip-components",
+        "include",
+        "exclude",
+    ];
+    const PROFILE: &[&str] = &["include", "exclude", "mods"];
+    const LOAD_ORDER: &[&str] = &["file", "format", "template"];
+    const POLICY: &[&str] = &["allow", "block"];
+
+    fn unknown_mod_keys(mods: &toml::Table, prefix: &str, keys: &mut Vec<String>) {
+        for (name, m) in mods {
+            if let Some(m) = m.as_table() {
+                unknown(m, MOD, &format!("{prefix}{name}."), keys);
+            }
+        }
+    }
+
+    fn unknown(table: &toml::Table, known: &[&str], prefix: &str, keys: &mut Vec<String>) {
+        for key in table.keys() {
+            if !known.contains(&key.as_str()) {
+                keys.push(format!("{prefix}{key}"));
+            }
+        }
+    }
+
+    let Ok(root) = content.parse::<toml::Table>() else {
+        return vec![];
+    };
+    let mut keys = vec![];
+    unknown(&root, TOP_LEVEL, "", &mut keys);
+    if let Some(game) = root.get("game").and_then(toml::Value::as_table) {
+        unknown(game, GAME, "game.", &mut keys);
+    }
+    if let Some(mods) = root.get("mods").and_then(toml::Value::as_table) {
+        unknown_mod_keys(mods, "mods.", &mut keys);
+    }
+    if let Some(load_order) = root.get("load-order").and_then(toml::Value::as_table) {
+        unknown(load_order, LOAD_ORDER, "load-order.", &mut keys);
+    }
+    if let Some(policy) = root.get("policy").and_then(toml::Value::as_table) {
+        unknown(policy, POLICY, "policy.", &mut keys);
+    }
+    let profiles = root.get("profile").and_then(toml::Value::as_table);
+    for (name, profile) in profiles.into_iter().flatten() {
+        let Some(profile) = profile.as_table() else {
+            continue;
+        };
+        unknown(profile, PROFILE, &format!("profile.{name}."), &mut keys);
+        if let Some(mods) = profile.get("mods").and_then(toml::Value::as_table) {
+            unknown_mod_keys(mods, &format!("profile.{name}.mods."), &mut keys);
+        }
+    }
+    keys
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -202,10 +514,13 @@ mod tests {
         let mod1 = ModDependency::Simple(Identifier::Id(1));
         let mod2 = ModDependency::Simple(Identifier::NameId("mod2".to_string()));
         let mod3 = ModDependency::Detailed(DetailedModDependency {
-            id: Identifier::Id(3),
+            id: Some(Identifier::Id(3)),
             with_dependencies: Some(true),
             file: None,
             version: None,
+            priority: None,
+            local: None,
+            extract: Extract::default(),
         });
         let mut expected = ModDependencies::new();
         expected.insert("mod1".to_string(), mod1);
@@ -223,6 +538,116 @@ mod tests {
         assert_eq!(expected, actual.unwrap());
     }
 
+    #[test]
+    fn local_sources() {
+        let raw = r#"
+        mymod = { path = "../mymod", strip-components = 1 }
+        mymap = { path = "../mymap.zip", target = "maps/{name}" }
+        maps = { id = 2, path = "maps" }
+        "#;
+        let mods: ModDependencies = toml::from_str(raw).unwrap();
+        let mymod = &mods["mymod"];
+        assert_eq!(mymod.id(), None);
+        assert_eq!(mymod.local(), Some("../mymod"));
+        assert_eq!(mymod.extract().unwrap().path, None);
+        let mymap = &mods["mymap"];
+        assert_eq!(mymap.local(), Some("../mymap.zip"));
+        assert_eq!(
+            mymap.extract().unwrap().path.as_deref(),
+            Some("maps/{name}")
+        );
+        let maps = &mods["maps"];
+        assert_eq!(maps.local(), None);
+        assert_eq!(maps.extract().unwrap().path.as_deref(), Some("maps"));
+
+        assert!(toml::from_str::<ModDependencies>("mod1 = { version = '1.0' }").is_err());
+        let target = "mod1 = { id = 1, target = 'maps' }";
+        assert!(toml::from_str::<ModDependencies>(target).is_err());
+    }
+
+    #[test]
+    fn unknown_keys() {
+        let raw = r#"
+        [game]
+        id = 1
+        with-dependency = true
+
+        [mods]
+        mod1 = 1
+        mod2 = { id = 2, verison = "1.0" }
+
+        [other]
+        "#;
+        let keys = super::unknown_keys(raw);
+        assert_eq!(keys, ["other", "game.with-dependency", "mods.mod2.verison"]);
+    }
+
+    #[test]
+    fn profiles() {
+        let raw = r#"
+        [game]
+        id = 1
+
+        [mods]
+        mod1 = 1
+        mod2 = 2
+        mod3 = 3
+
+        [profile.server]
+        exclude = ["mod2"]
+
+        [profile.server.mods]
+        admin = "admin-tools"
+
+        [profile.minimal]
+        include = ["mod1"]
+
+        [profile.broken]
+        include = ["mod4"]
+        "#;
+        let manifest: ModioManifest = toml::from_str(raw).unwrap();
+        let names = |profile| {
+            let mods = manifest.select_mods(profile, Features::All).unwrap();
+            mods.into_keys().collect::<Vec<_>>()
+        };
+        assert_eq!(names(None), ["mod1", "mod2", "mod3"]);
+        assert_eq!(names(Some("server")), ["admin", "mod1", "mod3"]);
+        assert_eq!(names(Some("minimal")), ["mod1"]);
+        assert!(manifest.select_mods(Some("broken"), Features::All).is_err());
+        assert!(manifest.select_mods(Some("client"), Features::All).is_err());
+    }
+
+    #[test]
+    fn features() {
+        let raw = r#"
+        [game]
+        id = 1
+
+        [mods]
+        mod1 = 1
+        texpack = 2
+        lighting = 3
+
+        [features]
+        hd-textures = ["texpack", "lighting"]
+        night = ["lighting"]
+        "#;
+        let manifest: ModioManifest = toml::from_str(raw).unwrap();
+        let names = |features| {
+            let mods = manifest.select_mods(None, features).unwrap();
+            mods.into_keys().collect::<Vec<_>>()
+        };
+        assert_eq!(names(Features::Selected(&[])), ["mod1"]);
+        let night = ["night".to_string()];
+        assert_eq!(names(Features::Selected(&night)), ["lighting", "mod1"]);
+        assert_eq!(names(Features::All), ["lighting", "mod1", "texpack"]);
+
+        let unknown = ["sound".to_string()];
+        assert!(manifest
+            .select_mods(None, Features::Selected(&unknown))
+            .is_err());
+    }
+
     #[test]
     fn manifest() {
         let raw = r#"
@@ -245,22 +670,31 @@ mod tests {
         let mod1 = ModDependency::Simple(Identifier::Id(1));
         let mod2 = ModDependency::Simple(Identifier::NameId("mod2".to_string()));
         let mod3 = ModDependency::Detailed(DetailedModDependency {
-            id: Identifier::Id(3),
+            id: Some(Identifier::Id(3)),
             with_dependencies: Some(true),
             file: None,
             version: None,
+            priority: None,
+            local: None,
+            extract: Extract::default(),
         });
         let mod4 = ModDependency::Detailed(DetailedModDependency {
-            id: Identifier::NameId("mod4".to_string()),
+            id: Some(Identifier::NameId("mod4".to_string())),
             with_dependencies: None,
             file: None,
             version: None,
+            priority: None,
+            local: None,
+            extract: Extract::default(),
         });
         let mod5 = ModDependency::Detailed(DetailedModDependency {
-            id: Identifier::NameId("mod5".to_string()),
+            id: Some(Identifier::NameId("mod5".to_string())),
             with_dependencies: None,
             file: None,
             version: Some("1.2".to_string()),
+            priority: None,
+            local: None,
+            extract: Extract::default(),
         });
         let mut mods = ModDependencies::new();
         mods.insert("mod1".to_string(), mod1);
@@ -274,6 +708,10 @@ mod tests {
                 with_dependencies: None,
             },
             mods: Some(mods),
+            profile: BTreeMap::new(),
+            features: BTreeMap::new(),
+            load_order: None,
+            policy: None,
         };
 
         let actual = toml::from_str(raw);
@@ -1,9 +1,14 @@
+use std::io;
+use std::path::Path;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use bytes::{Bytes, BytesMut};
-use futures::Sink;
+use futures::{Sink, StreamExt};
 use md5::digest::Digest;
+use tokio::fs::File;
+use tokio::io::BufReader;
+use tokio_util::io::ReaderStream;
 
 #[derive(Default)]
 pub struct Md5(md5::Md5);
@@ -14,6 +19,16 @@ impl Md5 {
     }
 }
 
+/// Calculate the MD5 checksum of the file at `path` as lower hex string.
+pub async fn file_hash(path: &Path) -> io::Result<String> {
+    let r = File::open(path).await?;
+    let r = BufReader::with_capacity(512 * 512, r);
+    let st = ReaderStream::new(r);
+    let mut md5 = Md5::default();
+    st.forward(&mut md5).await?;
+    Ok(md5.into_lower_hex())
+}
+
 impl Sink<Bytes> for Md5 {
     type Error = std::io::Error;
 
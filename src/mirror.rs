@@ -0,0 +1,165 @@
+//! The index of a mirror directory created by `modiom mirror`.
+//!
+//! The archives of a mirror are stored in the layout of the download cache, see
+//! [`Cache`](crate::cache::Cache), and `index.json` describes the mirrored mods and modfiles.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use modio::types::files::File;
+use modio::types::id::{FileId, GameId, ModId};
+use modio::types::mods::Mod;
+
+use crate::cache::check_filename;
+use crate::errors::Error;
+use crate::lockfile::LockedMod;
+use crate::utils;
+use crate::Result;
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Index {
+    #[serde(default)]
+    pub mods: Vec<MirroredMod>,
+}
+
+/// A modfile of the mirror together with the metadata of its mod.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirroredMod {
+    pub game: GameId,
+    pub id: ModId,
+    pub name_id: String,
+    pub name: String,
+    pub file: FileId,
+    pub filename: String,
+    pub version: Option<String>,
+    pub size: u64,
+    pub md5: String,
+    /// Path of the archive relative to the mirror directory.
+    pub path: String,
+    /// The mods the mod depends on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<ModId>,
+    /// The maturity flags of the mod, see `MaturityOption`.
+    #[serde(default)]
+    pub maturity_option: Option<u8>,
+}
+
+impl MirroredMod {
+    pub fn new(m: &Mod, file: &File, dependencies: Vec<ModId>) -> std::result::Result<Self, Error> {
+        check_filename(&file.filename)?;
+        let md5 = file.filehash.md5.to_ascii_lowercase();
+        Ok(Self {
+            game: m.game_id,
+            id: m.id,
+            name_id: m.name_id.clone(),
+            name: m.name.clone(),
+            file: file.id,
+            filename: file.filename.clone(),
+            version: file.version.clone(),
+            size: file.filesize,
+            path: format!("{}-{md5}/{}", file.id, file.filename),
+            md5,
+            dependencies,
+            maturity_option: Some(m.maturity_option.bits()),
+        })
+    }
+
+    /// Returns the lock entry of the mirrored modfile for the mod `name`.
+    pub fn locked(&self, name: &str) -> LockedMod {
+        LockedMod {
+            name: name.to_owned(),
+            id: Some(self.id),
+            name_id: self.name_id.clone(),
+            file: Some(self.file),
+            filename: self.filename.clone(),
+            version: self.version.clone(),
+            size: self.size,
+            md5: self.md5.clone(),
+            dependency_of: None,
+            dependencies: self.dependencies.clone(),
+            local: None,
+            maturity_option: self.maturity_option,
+        }
+    }
+}
+
+impl Index {
+    /// Add the modfile to the index. Entries of other modfiles of the mod are kept, so the mirror
+    /// can serve projects that lock different versions.
+    pub fn add(&mut self, entry: MirroredMod) {
+        self.mods.retain(|m| m.file != entry.file);
+        self.mods.push(entry);
+        self.mods.sort_by_key(|m| (m.game, m.id, m.file));
+    }
+}
+
+/// Returns the path of the index of the mirror directory `dir`.
+pub fn path_for(dir: &Path) -> PathBuf {
+    dir.join("index.json")
+}
+
+/// Read the index of the mirror directory. Returns an empty index if the file doesn't exist.
+pub fn read(dir: &Path) -> Result<Index> {
+    let path = path_for(dir);
+    let content = match utils::read(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Index::default()),
+        Err(e) => return Err(e.into()),
+    };
+    let index = serde_json::from_str(&content).map_err(|e| {
+        Error::usage(format!(
+            "failed to parse mirror index at `{}`: {e}",
+            path.display()
+        ))
+    })?;
+    Ok(index)
+}
+
+pub fn write(dir: &Path, index: &Index) -> Result<()> {
+    let path = path_for(dir);
+    let mut content = serde_json::to_string_pretty(index)?;
+    content.push('\n');
+    match fs::write(&path, content) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(format!("Failed to write {}: {}", path.display(), e).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u64, file: u64) -> MirroredMod {
+        MirroredMod {
+            game: GameId::new(5),
+            id: ModId::new(id),
+            name_id: format!("mod-{id}"),
+            name: format!("Mod {id}"),
+            file: FileId::new(file),
+            filename: format!("mod{id}.zip"),
+            version: None,
+            size: 1024,
+            md5: "2d4a0e2d7273db6b0a94b0740a88ad0d".to_owned(),
+            path: format!("{file}-2d4a0e2d7273db6b0a94b0740a88ad0d/mod{id}.zip"),
+            dependencies: vec![],
+            maturity_option: Some(0),
+        }
+    }
+
+    #[test]
+    fn index() {
+        let mut index = Index::default();
+        index.add(entry(2, 21));
+        index.add(entry(1, 11));
+        index.add(entry(2, 20));
+        index.add(entry(2, 21));
+        let files = index.mods.iter().map(|m| m.file.get()).collect::<Vec<_>>();
+        assert_eq!(files, [11, 20, 21]);
+
+        let json = serde_json::to_string(&index).unwrap();
+        assert_eq!(serde_json::from_str::<Index>(&json).unwrap(), index);
+    }
+}
@@ -0,0 +1,246 @@
+//! The content policy of the `[policy]` sections of `~/.modio/config.toml` and `Modio.toml`.
+
+use modio::types::id::ModId;
+use modio::types::mods::{MaturityOption, Mod};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{Error, ErrorKind};
+use crate::manifest::{Identifier, ModioManifest};
+
+/// A maturity flag of mods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Maturity {
+    Alcohol,
+    Drugs,
+    Violence,
+    Explicit,
+}
+
+impl Maturity {
+    fn option(self) -> MaturityOption {
+        match self {
+            Maturity::Alcohol => MaturityOption::ALCOHOL,
+            Maturity::Drugs => MaturityOption::DRUGS,
+            Maturity::Violence => MaturityOption::VIOLENCE,
+            Maturity::Explicit => MaturityOption::EXPLICIT,
+        }
+    }
+}
+
+const NAMES: [(MaturityOption, &str); 4] = [
+    (MaturityOption::ALCOHOL, "alcohol"),
+    (MaturityOption::DRUGS, "drugs"),
+    (MaturityOption::VIOLENCE, "violence"),
+    (MaturityOption::EXPLICIT, "explicit"),
+];
+
+/// Returns the names of the maturity flags, e.g. `alcohol, violence`.
+pub fn describe(flags: MaturityOption) -> String {
+    if flags.is_empty() {
+        return "none".to_owned();
+    }
+    NAMES
+        .iter()
+        .filter(|(flag, _)| flags.contains(*flag))
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Mods identified by id or name-id that are allowed or blocked.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModLists {
+    /// Only these mods are allowed if set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow: Option<Vec<Identifier>>,
+    /// These mods are never allowed, even as dependencies.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub block: Vec<Identifier>,
+}
+
+/// Restrictions for the mods that are searched, downloaded and installed.
+#[derive(Debug, Default, Clone)]
+pub struct Policy {
+    /// The maturity flags mods are allowed to have. `None` allows all mods.
+    pub maturity: Option<MaturityOption>,
+    pub block: Vec<Identifier>,
+    /// The allow lists of the config and the manifest. Mods must be on every list.
+    pub allow: Vec<Vec<Identifier>>,
+}
+
+impl Policy {
+    pub fn new(maturity: Option<&[Maturity]>, lists: &ModLists) -> Self {
+        let maturity = maturity.map(|flags| {
+            flags
+                .iter()
+                .fold(MaturityOption::empty(), |acc, m| acc | m.option())
+        });
+        Self {
+            maturity,
+            block: vec![],
+            allow: vec![],
+        }
+        .merge(lists)
+    }
+
+    /// Returns the policy extended with the allow and block lists of a manifest.
+    pub fn merge(&self, lists: &ModLists) -> Self {
+        let mut policy = self.clone();
+        policy.block.extend(lists.block.iter().cloned());
+        policy.allow.extend(lists.allow.clone());
+        policy
+    }
+
+    /// Returns the policy extended with the allow and block lists of the manifest, if any.
+    pub fn for_manifest(&self, manifest: &ModioManifest) -> Self {
+        match &manifest.policy {
+            Some(lists) => self.merge(lists),
+            None => self.clone(),
+        }
+    }
+
+    /// Refuse mods on a block list or missing from an allow list. Mods without an id, e.g. from
+    /// a local source, are only matched by name-id.
+    pub fn check_listed(&self, id: Option<ModId>, name_id: &str) -> Result<(), Error> {
+        let matches = |ident: &Identifier| match ident {
+            Identifier::Id(i) => id.is_some_and(|id| *i == id.get()),
+            Identifier::NameId(n) => n == name_id,
+        };
+        let refused = if self.block.iter().any(matches) {
+            "is blocked by"
+        } else if !self.allow.iter().all(|list| list.iter().any(matches)) {
+            "is not on the allow list of"
+        } else {
+            return Ok(());
+        };
+        let name = match id {
+            Some(id) => format!("`{name_id}` ({id})"),
+            None => format!("`{name_id}`"),
+        };
+        Err(Error::new(
+            ErrorKind::Other,
+            format!("mod {name} {refused} the content policy"),
+        ))
+    }
+
+    /// Returns the maturity flags that aren't allowed by the policy.
+    pub fn denied(&self, flags: MaturityOption) -> MaturityOption {
+        match self.maturity {
+            Some(allowed) => flags.difference(allowed),
+            None => MaturityOption::empty(),
+        }
+    }
+
+    /// Returns all values of the `maturity_option` field that are allowed by the policy.
+    pub fn allowed_values(&self) -> Option<Vec<u8>> {
+        let allowed = self.maturity?;
+        let values = (0..=MaturityOption::all().bits())
+            .filter(|bits| allowed.contains(MaturityOption::from_bits_truncate(*bits)))
+            .collect();
+        Some(values)
+    }
+
+    /// Refuse mods that are blocked, not allowed or have maturity flags that exceed the policy.
+    pub fn check(&self, mod_: &Mod) -> Result<(), Error> {
+        self.check_listed(Some(mod_.id), &mod_.name_id)?;
+        self.check_maturity(&mod_.name_id, Some(mod_.maturity_option))
+    }
+
+    /// Refuse maturity flags that exceed the policy. Unknown flags, e.g. of lock entries written
+    /// before the flags were recorded, are refused if the policy restricts the maturity.
+    pub fn check_maturity(
+        &self,
+        name_id: &str,
+        flags: Option<MaturityOption>,
+    ) -> Result<(), Error> {
+        let Some(flags) = flags else {
+            if self.maturity.is_none() {
+                return Ok(());
+            }
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!(
+                    "the maturity flags of mod `{name_id}` are unknown, which the content policy doesn't allow"
+                ),
+            ));
+        };
+        let denied = self.denied(flags);
+        if denied.is_empty() {
+            return Ok(());
+        }
+        Err(Error::new(
+            ErrorKind::Other,
+            format!(
+                "mod `{name_id}` is flagged as {}, which the content policy doesn't allow",
+                describe(denied)
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maturity() {
+        let policy = Policy::new(
+            Some(&[Maturity::Alcohol, Maturity::Violence]),
+            &ModLists::default(),
+        );
+        let flags = MaturityOption::ALCOHOL | MaturityOption::DRUGS | MaturityOption::EXPLICIT;
+        assert_eq!(describe(policy.denied(flags)), "drugs, explicit");
+        assert!(policy.denied(MaturityOption::VIOLENCE).is_empty());
+        assert_eq!(policy.allowed_values(), Some(vec![0, 1, 4, 5]));
+
+        assert!(policy
+            .check_maturity("m", Some(MaturityOption::ALCOHOL))
+            .is_ok());
+        assert!(policy
+            .check_maturity("m", Some(MaturityOption::DRUGS))
+            .is_err());
+        assert!(policy.check_maturity("m", None).is_err());
+
+        let policy = Policy::default();
+        assert!(policy.denied(MaturityOption::all()).is_empty());
+        assert!(policy.check_maturity("m", None).is_ok());
+        assert_eq!(policy.allowed_values(), None);
+        assert_eq!(describe(MaturityOption::empty()), "none");
+    }
+
+    #[test]
+    fn mod_lists() {
+        let lists = ModLists {
+            allow: None,
+            block: vec![Identifier::Id(3), Identifier::NameId("bad".to_owned())],
+        };
+        let policy = Policy::new(None, &lists);
+        assert!(policy.check_listed(Some(ModId::new(1)), "good").is_ok());
+        assert!(policy.check_listed(Some(ModId::new(3)), "other").is_err());
+        assert!(policy.check_listed(Some(ModId::new(4)), "bad").is_err());
+        assert!(policy.check_listed(None, "bad").is_err());
+
+        let manifest = ModLists {
+            allow: Some(vec![
+                Identifier::Id(1),
+                Identifier::NameId("bad".to_owned()),
+            ]),
+            block: vec![],
+        };
+        let policy = policy.merge(&manifest);
+        assert!(policy.check_listed(Some(ModId::new(1)), "good").is_ok());
+        let err = policy
+            .check_listed(Some(ModId::new(2)), "other")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "mod `other` (2) is not on the allow list of the content policy"
+        );
+        let err = policy.check_listed(Some(ModId::new(4)), "bad").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "mod `bad` (4) is blocked by the content policy"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+
+/// The delay after hitting the rate limit if the response doesn't say how long to wait.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Retry policy for idempotent requests like API `GET` requests and downloads.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+/// The decision for a failed request.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Retry {
+    /// The error is permanent.
+    No,
+    /// The error is transient, retry after the next backoff delay.
+    Backoff,
+    /// The rate limit is exhausted, retry after the given duration.
+    After(Duration),
+}
+
+/// The rate limit was reached while downloading a file.
+#[derive(Debug)]
+pub struct RateLimited {
+    pub retry_after: Duration,
+}
+
+impl StdError for RateLimited {}
+
+impl RateLimited {
+    /// Returns the delay of a response from its `Retry-After` header. Responses with the status
+    /// `429 Too Many Requests` but without the header wait for the rate limit window.
+    pub fn from_response(status: StatusCode, headers: &HeaderMap) -> Option<Self> {
+        if status.is_success() {
+            return None;
+        }
+        let secs = headers
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok());
+        let retry_after = match secs {
+            Some(secs) => Duration::from_secs(secs),
+            None if status == StatusCode::TOO_MANY_REQUESTS => RATE_LIMIT_WINDOW,
+            None => return None,
+        };
+        Some(Self { retry_after })
+    }
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "rate limit reached. Try again in {:?}.",
+            self.retry_after
+        )
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(3)
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(retries: u32) -> Self {
+        Self {
+            retries,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    /// Returns the exponential backoff delay for the given attempt starting with `0`.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// Run `f` until it succeeds, fails with a permanent error or the retries are used up.
+    pub async fn run<T, E, F, Fut>(&self, mut f: F) -> Result<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: Retryable + fmt::Display,
+    {
+        let mut attempt = 0;
+        loop {
+            match f().await {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < self.retries => {
+                    let delay = match e.retry() {
+                        Retry::No => return Err(e),
+                        Retry::Backoff => self.delay(attempt),
+                        Retry::After(delay) => delay,
+                    };
+                    attempt += 1;
+                    eprintln!(
+                        "warning: {e} (retrying in {}s, {} tries remaining)",
+                        delay.as_secs(),
+                        self.retries - attempt + 1,
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Errors that can tell whether the failed request should be retried.
+pub trait Retryable {
+    fn retry(&self) -> Retry;
+}
+
+impl Retryable for modio::Error {
+    fn retry(&self) -> Retry {
+        if self.is_ratelimited() {
+            // modio reads the `Retry-After` header of the response but only exposes the delay as
+            // part of the error message. Without it the rate limit window is waited out.
+            let delay = find_duration(&self.to_string()).unwrap_or(RATE_LIMIT_WINDOW);
+            return Retry::After(delay);
+        }
+        if let Some(status) = self.status() {
+            return retry_status(status);
+        }
+        if self.is_auth() || self.is_validation() || self.is_builder() || self.is_download() {
+            return Retry::No;
+        }
+        match self.source() {
+            Some(source) => retry_source(source),
+            None => Retry::No,
+        }
+    }
+}
+
+impl Retryable for reqwest::Error {
+    fn retry(&self) -> Retry {
+        if let Some(status) = self.status() {
+            return retry_status(status);
+        }
+        if self.is_timeout() || self.is_connect() || self.is_request() || self.is_body() {
+            Retry::Backoff
+        } else {
+            Retry::No
+        }
+    }
+}
+
+impl Retryable for Box<dyn StdError> {
+    fn retry(&self) -> Retry {
+        retry_source(&**self)
+    }
+}
+
+fn retry_source(e: &(dyn StdError + 'static)) -> Retry {
+    if let Some(e) = e.downcast_ref::<RateLimited>() {
+        Retry::After(e.retry_after)
+    } else if let Some(e) = e.downcast_ref::<modio::Error>() {
+        e.retry()
+    } else if let Some(e) = e.downcast_ref::<reqwest::Error>() {
+        e.retry()
+    } else {
+        Retry::No
+    }
+}
+
+fn retry_status(status: StatusCode) -> Retry {
+    if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+        Retry::Backoff
+    } else {
+        Retry::No
+    }
+}
+
+/// Returns the first duration in the `Debug` format of `Duration`, e.g. `60s` or `1.5s`, of the
+/// message regardless of the surrounding wording.
+fn find_duration(msg: &str) -> Option<Duration> {
+    msg.split(|c: char| c.is_whitespace() || c == ',' || c == ':')
+        .find_map(|word| parse_duration(word.trim_end_matches('.')))
+}
+
+fn parse_duration(s: &str) -> Option<Duration> {
+    let (value, scale) = if let Some(v) = s.strip_suffix("ms") {
+        (v, 1e-3)
+    } else if let Some(v) = s.strip_suffix("µs") {
+        (v, 1e-6)
+    } else if let Some(v) = s.strip_suffix("ns") {
+        (v, 1e-9)
+    } else {
+        (s.strip_suffix('s')?, 1.0)
+    };
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+        return None;
+    }
+    let secs = value.parse::<f64>().ok()? * scale;
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay() {
+        let policy = RetryPolicy::new(10);
+        assert_eq!(policy.delay(0), Duration::from_secs(1));
+        assert_eq!(policy.delay(1), Duration::from_secs(2));
+        assert_eq!(policy.delay(3), Duration::from_secs(8));
+        assert_eq!(policy.delay(8), Duration::from_secs(60));
+        assert_eq!(policy.delay(40), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn retry_after() {
+        let mut headers = HeaderMap::new();
+        let limited = |status, headers: &HeaderMap| {
+            RateLimited::from_response(status, headers).map(|r| r.retry_after)
+        };
+        assert_eq!(
+            limited(StatusCode::TOO_MANY_REQUESTS, &headers),
+            Some(RATE_LIMIT_WINDOW)
+        );
+        assert_eq!(limited(StatusCode::SERVICE_UNAVAILABLE, &headers), None);
+
+        headers.insert(RETRY_AFTER, "30".parse().unwrap());
+        let delay = Some(Duration::from_secs(30));
+        assert_eq!(limited(StatusCode::TOO_MANY_REQUESTS, &headers), delay);
+        assert_eq!(limited(StatusCode::SERVICE_UNAVAILABLE, &headers), delay);
+        assert_eq!(limited(StatusCode::OK, &headers), None);
+    }
+
+    #[test]
+    fn durations_in_messages() {
+        let msg = format!(
+            "rate limit reached. Try again in {:?}.",
+            Duration::from_secs(60)
+        );
+        assert_eq!(find_duration(&msg), Some(Duration::from_secs(60)));
+        let msg = format!("retry after {:?}", Duration::from_millis(1500));
+        assert_eq!(find_duration(&msg), Some(Duration::from_millis(1500)));
+        let msg = format!("wait {:?}", Duration::from_millis(250));
+        assert_eq!(find_duration(&msg), Some(Duration::from_millis(250)));
+        assert_eq!(find_duration("unauthorized: tokens"), None);
+    }
+
+    #[test]
+    fn boxed_errors() {
+        let e: Box<dyn StdError> = Box::new(RateLimited {
+            retry_after: Duration::from_secs(5),
+        });
+        assert_eq!(e.retry(), Retry::After(Duration::from_secs(5)));
+
+        let e: Box<dyn StdError> = "checksum mismatch".into();
+        assert_eq!(e.retry(), Retry::No);
+    }
+}
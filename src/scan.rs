@@ -0,0 +1,84 @@
+//! The results of the virus scan mod.io runs on uploaded modfiles.
+
+use std::fmt;
+
+use modio::types::files::{File, VirusResult, VirusStatus};
+
+use crate::errors::Error;
+
+/// The outcome of the virus scan of a modfile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scan {
+    /// The scan completed without detections.
+    Clean,
+    /// The modfile isn't scanned yet or the scan failed.
+    Unscanned(VirusStatus),
+    /// The scan detected a malicious or potentially harmful file.
+    Flagged(VirusResult),
+}
+
+impl Scan {
+    pub fn new(status: VirusStatus, result: VirusResult) -> Self {
+        if result != VirusResult::NO_THREATS_DETECTED {
+            Scan::Flagged(result)
+        } else if status == VirusStatus::SCAN_COMPLETED {
+            Scan::Clean
+        } else {
+            Scan::Unscanned(status)
+        }
+    }
+
+    pub fn of(file: &File) -> Self {
+        Self::new(file.virus_scan.status, file.virus_scan.result)
+    }
+}
+
+impl fmt::Display for Scan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Scan::Clean => f.write_str("clean"),
+            Scan::Unscanned(VirusStatus::NOT_SCANNED) => f.write_str("not scanned"),
+            Scan::Unscanned(VirusStatus::IN_PROGRESS) => f.write_str("scan in progress"),
+            Scan::Unscanned(VirusStatus::TOO_LARGE_TO_SCAN) => f.write_str("too large to scan"),
+            Scan::Unscanned(VirusStatus::FILE_NOT_FOUND) => f.write_str("file not found"),
+            Scan::Unscanned(VirusStatus::ERROR_SCANNING) => f.write_str("scan failed"),
+            Scan::Unscanned(status) => write!(f, "unknown status {status}"),
+            Scan::Flagged(VirusResult::MALICIOUS) => f.write_str("malicious"),
+            Scan::Flagged(VirusResult::POTENTIALLY_HARMFUL) => f.write_str("potentially harmful"),
+            Scan::Flagged(result) => write!(f, "flagged ({result})"),
+        }
+    }
+}
+
+/// Refuse to download modfiles flagged by the virus scan. Unscanned modfiles are only allowed
+/// with `allow_unscanned`.
+pub fn check(file: &File, allow_unscanned: bool) -> Result<(), Error> {
+    match Scan::of(file) {
+        Scan::Clean => Ok(()),
+        Scan::Unscanned(_) if allow_unscanned => Ok(()),
+        scan @ Scan::Unscanned(_) => Err(Error::integrity(format!(
+            "`{}` has not passed the mod.io virus scan ({scan}), use `--allow-unscanned` to download it anyway",
+            file.filename
+        ))),
+        scan @ Scan::Flagged(_) => Err(Error::integrity(format!(
+            "`{}` was flagged as {scan} by the mod.io virus scan, refusing to download it",
+            file.filename
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_outcome() {
+        let scan = |status, result| Scan::new(VirusStatus::new(status), VirusResult::new(result));
+        assert_eq!(scan(1, 0), Scan::Clean);
+        assert_eq!(scan(0, 0).to_string(), "not scanned");
+        assert_eq!(scan(2, 0).to_string(), "scan in progress");
+        assert_eq!(scan(1, 1).to_string(), "malicious");
+        assert_eq!(scan(1, 2).to_string(), "potentially harmful");
+        assert!(matches!(scan(5, 1), Scan::Flagged(_)));
+    }
+}
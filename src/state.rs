@@ -0,0 +1,147 @@
+//! The installed files of the mods, stored as `.modiom/state.toml` in the manifest directory.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use modio::types::id::{FileId, ModId};
+
+use crate::errors::Error;
+use crate::lockfile::LockedMod;
+use crate::manifest::Extract;
+use crate::utils;
+use crate::Result;
+
+const HEADER: &str = "\
+# This file is automatically generated by modiom.
+# It is not intended for manual editing.
+";
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct State {
+    #[serde(rename = "mod", default)]
+    pub mods: Vec<InstalledMod>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct InstalledMod {
+    /// Key of the mod in the manifest or the name-id of a dependency.
+    pub name: String,
+    /// The mod id, `None` for mods installed from a local source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<ModId>,
+    /// The modfile id, `None` for mods installed from a local source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<FileId>,
+    /// The extract options used for the install. `None` if the archive was copied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extract: Option<Extract>,
+    #[serde(default)]
+    pub files: Vec<InstalledFile>,
+    /// Files of the modfile that are installed by another mod with a higher priority.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shadowed: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledFile {
+    /// Path relative to the manifest directory with `/` separators.
+    pub path: String,
+    pub size: u64,
+    pub md5: String,
+}
+
+impl State {
+    pub fn get(&self, name: &str) -> Option<&InstalledMod> {
+        self.mods.iter().find(|m| m.name == name)
+    }
+}
+
+impl InstalledMod {
+    /// Returns the copied archive of the lock entry. Installs without a state file only
+    /// recorded the archive in Modio.lock.
+    pub fn from_locked(locked: &LockedMod) -> Self {
+        Self {
+            name: locked.name.clone(),
+            id: locked.id,
+            file: locked.file,
+            extract: None,
+            files: vec![InstalledFile {
+                path: locked.filename.clone(),
+                size: locked.size,
+                md5: locked.md5.clone(),
+            }],
+            shadowed: vec![],
+        }
+    }
+
+    /// Returns true if all installed files still exist.
+    pub fn exists(&self, root: &Path) -> bool {
+        self.files.iter().all(|f| root.join(&f.path).is_file())
+    }
+
+    /// Returns the paths of all files of the modfile including the shadowed files.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        let files = self.files.iter().map(|f| f.path.as_str());
+        files.chain(self.shadowed.iter().map(String::as_str))
+    }
+
+    /// Remove the installed files except the files for which `keep` returns true and the
+    /// directories left empty.
+    pub fn remove_files<F>(&self, root: &Path, keep: F) -> io::Result<()>
+    where
+        F: Fn(&str) -> bool,
+    {
+        for f in self.files.iter().filter(|f| !keep(&f.path)) {
+            let path = root.join(&f.path);
+            match fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+            let mut dir = path.parent();
+            while let Some(d) = dir.filter(|d| *d != root) {
+                if fs::remove_dir(d).is_err() {
+                    break;
+                }
+                dir = d.parent();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the path of the state file for the install directory `root`.
+pub fn path_for(root: &Path) -> PathBuf {
+    root.join(".modiom").join("state.toml")
+}
+
+/// Read the state file at `path`. Returns an empty state if the file doesn't exist.
+pub fn read(path: &Path) -> Result<State> {
+    let content = match utils::read(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(State::default()),
+        Err(e) => return Err(e.into()),
+    };
+    let state = toml::from_str(&content).map_err(|e| {
+        Error::usage(format!(
+            "failed to parse install state at `{}`: {e}",
+            path.display()
+        ))
+    })?;
+    Ok(state)
+}
+
+pub fn write(path: &Path, state: &State) -> Result<()> {
+    let content = toml::to_string(state)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match fs::write(path, format!("{HEADER}\n{content}")) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(format!("Failed to write {}: {}", path.display(), e).into()),
+    }
+}
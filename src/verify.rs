@@ -0,0 +1,122 @@
+//! Checking the installed files against the sizes and checksums recorded in the install state.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::extract::to_slash;
+use crate::md5;
+use crate::state::InstalledMod;
+
+/// The installed files of a mod that differ from the install state.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    pub modified: Vec<String>,
+    pub missing: Vec<String>,
+    /// Files in the extract directory of the mod that aren't installed by any mod.
+    pub extra: Vec<String>,
+}
+
+impl Report {
+    /// Returns true if installed files are modified or missing.
+    pub fn is_broken(&self) -> bool {
+        !self.modified.is_empty() || !self.missing.is_empty()
+    }
+
+    pub fn is_ok(&self) -> bool {
+        !self.is_broken() && self.extra.is_empty()
+    }
+}
+
+/// Compare the installed files of the mod with their recorded size and MD5 checksum.
+pub async fn verify(root: &Path, installed: &InstalledMod) -> io::Result<Report> {
+    let mut report = Report::default();
+    for file in &installed.files {
+        let path = root.join(&file.path);
+        let size = match fs::metadata(&path) {
+            Ok(md) => md.len(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                report.missing.push(file.path.clone());
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        if size != file.size || md5::file_hash(&path).await? != file.md5 {
+            report.modified.push(file.path.clone());
+        }
+    }
+    Ok(report)
+}
+
+/// Returns the files below `dir` that aren't part of `known`. The paths are relative to `root`
+/// with `/` separators.
+pub fn extra_files(root: &Path, dir: &Path, known: &HashSet<&str>) -> io::Result<Vec<String>> {
+    let mut extra = vec![];
+    let mut dirs = vec![root.join(dir)];
+    while let Some(dir) = dirs.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            let rel = to_slash(path.strip_prefix(root).unwrap_or(&path));
+            if !known.contains(rel.as_str()) {
+                extra.push(rel);
+            }
+        }
+    }
+    extra.sort();
+    Ok(extra)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::InstalledFile;
+    use crate::utils::TempDir;
+    use modio::types::id::{FileId, ModId};
+
+    #[test]
+    fn modified_missing_extra() {
+        let tmp = TempDir::new("modiom-verify").unwrap();
+        let root = tmp.path();
+        fs::create_dir_all(root.join("mods/tex")).unwrap();
+        fs::write(root.join("mods/tex/a.pak"), "pak").unwrap();
+        fs::write(root.join("mods/tex/b.pak"), "changed").unwrap();
+        fs::write(root.join("mods/tex/notes.txt"), "").unwrap();
+
+        let file = |path: &str, size, md5: &str| InstalledFile {
+            path: path.to_owned(),
+            size,
+            md5: md5.to_owned(),
+        };
+        let installed = InstalledMod {
+            name: "tex".to_owned(),
+            id: Some(ModId::new(1)),
+            file: Some(FileId::new(1)),
+            extract: None,
+            files: vec![
+                file("mods/tex/a.pak", 3, "8d569333abbc9e26646dc6a398891324"),
+                file("mods/tex/b.pak", 7, "00000000000000000000000000000000"),
+                file("mods/tex/c.pak", 3, "8d569333abbc9e26646dc6a398891324"),
+            ],
+            shadowed: vec![],
+        };
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let report = rt.block_on(verify(root, &installed)).unwrap();
+        assert_eq!(report.modified, ["mods/tex/b.pak"]);
+        assert_eq!(report.missing, ["mods/tex/c.pak"]);
+
+        let known = installed.paths().collect();
+        let extra = extra_files(root, Path::new("mods"), &known).unwrap();
+        assert_eq!(extra, ["mods/tex/notes.txt"]);
+    }
+}
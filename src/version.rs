@@ -0,0 +1,99 @@
+//! Matching modfile versions against the `version` field of the manifest.
+//!
+//! A plain version like `1.2` selects the modfile with exactly this version. Versions starting
+//! with an operator (`^1.2`, `~1.4.1`, `>=2.0, <3`, `*`) are semver requirements matched against
+//! the versions of all modfiles.
+
+use semver::{Version, VersionReq};
+
+use crate::errors::Error;
+
+pub enum VersionSpec {
+    /// The modfile version must be equal.
+    Exact(String),
+    Req(VersionReq),
+}
+
+impl VersionSpec {
+    pub fn parse(spec: &str) -> Result<Self, Error> {
+        let spec = spec.trim();
+        if !spec.starts_with(['^', '~', '=', '<', '>', '*']) {
+            return Ok(VersionSpec::Exact(spec.to_owned()));
+        }
+        VersionReq::parse(spec)
+            .map(VersionSpec::Req)
+            .map_err(|e| Error::usage(format!("invalid version requirement `{spec}`: {e}")))
+    }
+
+    pub fn matches(&self, version: Option<&str>) -> bool {
+        match (self, version) {
+            (VersionSpec::Exact(spec), Some(version)) => spec == version,
+            (VersionSpec::Req(req), Some(version)) => {
+                parse_version(version).is_some_and(|v| req.matches(&v))
+            }
+            (_, None) => false,
+        }
+    }
+}
+
+/// Parse a modfile version leniently.
+///
+/// A leading `v` is ignored and missing minor and patch numbers are filled with zeros, so
+/// `v1.2` is read as `1.2.0`. Versions with more than three numbers or other formats like
+/// `beta 3` return `None` and never match a requirement.
+pub fn parse_version(version: &str) -> Option<Version> {
+    let version = version.trim();
+    let version = version.strip_prefix(['v', 'V']).unwrap_or(version);
+    let end = version.find(['-', '+']).unwrap_or(version.len());
+    let (numbers, rest) = version.split_at(end);
+
+    let parts = numbers.split('.').collect::<Vec<_>>();
+    if parts.len() > 3 || parts.iter().any(|p| p.parse::<u64>().is_err()) {
+        return None;
+    }
+    let mut normalized = parts.join(".");
+    for _ in parts.len()..3 {
+        normalized.push_str(".0");
+    }
+    Version::parse(&format!("{normalized}{rest}")).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lenient_versions() {
+        assert_eq!(parse_version("1.2.3"), Some(Version::new(1, 2, 3)));
+        assert_eq!(parse_version("v1.2"), Some(Version::new(1, 2, 0)));
+        assert_eq!(parse_version("2"), Some(Version::new(2, 0, 0)));
+        assert!(parse_version("1.0-beta.1").is_some_and(|v| !v.pre.is_empty()));
+        assert_eq!(parse_version("1.2.3.4"), None);
+        assert_eq!(parse_version("beta 3"), None);
+        assert_eq!(parse_version(""), None);
+    }
+
+    #[test]
+    fn specs() {
+        let spec = VersionSpec::parse("1.2").unwrap();
+        assert!(spec.matches(Some("1.2")));
+        assert!(!spec.matches(Some("1.2.0")));
+
+        let spec = VersionSpec::parse("^1.2").unwrap();
+        assert!(spec.matches(Some("1.2")));
+        assert!(spec.matches(Some("v1.9.1")));
+        assert!(!spec.matches(Some("2.0")));
+        assert!(!spec.matches(Some("final")));
+        assert!(!spec.matches(None));
+
+        let spec = VersionSpec::parse(">=2.0, <3").unwrap();
+        assert!(spec.matches(Some("2.5")));
+        assert!(!spec.matches(Some("3.0")));
+
+        let spec = VersionSpec::parse("~1.4.1").unwrap();
+        assert!(spec.matches(Some("1.4.7")));
+        assert!(!spec.matches(Some("1.5.0")));
+
+        assert!(VersionSpec::parse(">=abc").is_err());
+    }
+}